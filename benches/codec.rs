@@ -0,0 +1,42 @@
+use basehanja::Encoding;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Repack-mode encodings of every kind: ASCII (SIMD decoding), 8-bit and 16-bit, and 13-bit (no
+/// word-at-a-time path)
+const ENCODINGS: &[&str] = &["hex", "base64", "hiragana", "kanji", "hangul"];
+
+fn data() -> Vec<u8> {
+    (0..64 * 1024)
+        .map(|x: u32| (x.wrapping_mul(2654435761) >> 13) as u8)
+        .collect()
+}
+
+fn encode(c: &mut Criterion) {
+    let data = data();
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    for name in ENCODINGS {
+        let codec = name.parse::<&Encoding>().unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &data, |b, data| {
+            b.iter(|| codec.encode(data))
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let data = data();
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    for name in ENCODINGS {
+        let codec = name.parse::<&Encoding>().unwrap();
+        let text = codec.encode(&data);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &text, |b, text| {
+            b.iter(|| codec.decode(text).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
@@ -3,6 +3,8 @@ use basehanja::{decode_utf8, encode_utf8, get_encodings};
 use clap::{App, Arg};
 
 fn main() {
+    let names = get_encodings();
+    let names = names.iter().map(String::as_str).collect::<Vec<&str>>();
     let matches = App::new("CLI example")
         .arg(
             Arg::with_name("action")
@@ -11,7 +13,7 @@ fn main() {
         )
         .arg(
             Arg::with_name("encoding")
-                .possible_values(&get_encodings())
+                .possible_values(&names)
                 .required(true),
         )
         .arg(Arg::with_name("text").required(true))
@@ -19,14 +21,14 @@ fn main() {
 
     let codec = matches.value_of("encoding").unwrap();
     let text = matches.value_of("text").unwrap();
-    // note: will panic if any error, because it tries to construct a JsValue on non-wasm...
     let res = match matches.value_of("action") {
         Some("enc") => encode_utf8(text, codec),
         Some("dec") => decode_utf8(text, codec),
         _ => unreachable!(),
     };
 
-    if let Ok(res) = res {
-        println!("{}", res);
+    match res {
+        Ok(res) => println!("{}", res),
+        Err(e) => eprintln!("error: {}", e),
     }
 }
@@ -14,7 +14,7 @@ fn main() {
     println!("|--------|----------|------:|------:|-:|-:|:-----------|:---------:|:---------:|");
     for codec in get_encodings() {
         for (sample, desc) in SAMPLES {
-            let enc = encode_utf8(sample, codec).unwrap();
+            let enc = encode_utf8(sample, &codec).unwrap();
             let c_len = sample.chars().count();
             let b_len = sample.len();
             let c_len2 = enc.chars().count();
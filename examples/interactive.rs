@@ -49,15 +49,15 @@ fn main() {
         };
 
         let (arg, rest) = pop_arg!(rest);
-        // if any error is raised, it will crash, because it constructs a JsValue...
         let res = if encode {
             basehanja::encode_utf8(rest, arg)
         } else {
             basehanja::decode_utf8(rest, arg)
         };
 
-        if let Ok(s) = res {
-            println!("{}", s);
+        match res {
+            Ok(s) => println!("{}", s),
+            Err(e) => eprintln!("error: {}", e),
         }
     }
 }
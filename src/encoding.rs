@@ -1,37 +1,120 @@
-use crate::repack::{uVar, RepackIterator};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::iter::once;
+use core::ops::Range;
 
+use serde::{Deserialize, Serialize};
+
+use crate::error::DecodeError;
+use crate::radix;
+use crate::repack::{self, uVar, BitOrder, RepackIterator};
+
+pub mod builder;
 mod config;
-use config::ENCODINGS;
+pub mod definition;
+#[cfg(feature = "std")]
+pub mod detect;
+pub mod diagnostics;
+pub mod header;
+mod lookup;
+pub mod options;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod stream;
+use lookup::{AsciiLookup, Lookup};
 
 pub struct Encoding {
-    pub name: &'static str,
-    pub long_name: &'static str,
+    pub name: String,
+    pub long_name: String,
+    /// stable identifier used by headers, see `Encoding::encode_with_header()`
+    id: Option<u16>,
     char_space: CharSpace,
+    /// `char_space.num_chars()`, and the bits of a repack-mode char, see `bitcount()`
+    num_chars: usize,
+    bitcount: u8,
     pad_char: PadType,
+    mode: Mode,
+    /// how repack mode packs bits into chars
+    bit_order: BitOrder,
+    /// bits held by the chars past the first `2^bitcount()`, see `EncodingBuilder::tail_bits()`
+    tail_bits: Option<u8>,
+    /// alphabet indices of the tail chars, if any
+    tails: Option<Range<uVar>>,
+    lookup: Lookup,
+    /// byte tables for the word-at-a-time paths, if the alphabet is all ASCII
+    ascii: Option<AsciiLookup>,
+    /// UTF-8 length shared by every char of the alphabet, if any
+    char_width: Option<usize>,
+    /// otherwise, the `(index, UTF-8 length)` of every char whose length differs from the previous
+    /// one's, to size texts without looking their chars up
+    width_steps: Vec<(uVar, usize)>,
+    /// longest UTF-8 and UTF-16 length of the chars of the alphabet
+    max_width: (usize, usize),
+}
+
+/// Length of an encoded text, see `Encoding::encoded_len()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodedLen {
+    pub chars: usize,
+    pub utf8: usize,
+    pub utf16: usize,
 }
 
-enum CharSpace {
-    Concrete(&'static str),
-    Intervals(&'static [(char, char)]),
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CharSpace {
+    /// every char of the string, in order
+    Concrete(String),
+    /// every char of the inclusive intervals, in order
+    Intervals(Vec<(char, char)>),
 }
 
-enum PadType {
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadType {
     /// "padding" is the same as in base64
     BlockPad(char),
     /// "padding" signifies how many chars to drop when decoding
     DropPad(char),
+    /// `BlockPad`, but left out when encoding (like unpadded base64url). Decoding still accepts it,
+    /// and splits concatenated parts on it.
+    OmitPad(char),
+    /// `OmitPad`, but the bits of the last char past the data are set instead of clear (like in
+    /// Base32768)
+    OnesPad(char),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// bits are repacked into `bitcount()`-sized chars, see `RepackIterator`
+    #[default]
+    Repack,
+    /// the input is a single big number, written in base `num_chars()`. The pad char terminates it
+    Radix,
+    /// fixed blocks of bytes are written as fixed-length base `num_chars()` numbers (like Ascii85),
+    /// see `block_size()`
+    BlockRadix,
 }
 
 use CharSpace::*;
+use Mode::*;
 use PadType::*;
 
 // taboo AFAIK; I'm doing it just because I can
-impl std::ops::Deref for PadType {
+impl core::ops::Deref for PadType {
     type Target = char;
     fn deref(&self) -> &Self::Target {
         match self {
             Self::BlockPad(c) => c,
             Self::DropPad(c) => c,
+            Self::OmitPad(c) => c,
+            Self::OnesPad(c) => c,
         }
     }
 }
@@ -42,207 +125,928 @@ macro_rules! cp_len {
     };
 }
 
-macro_rules! div_ceil {
-    ($div:expr, $by:expr) => {{
-        let mut a = $div;
-        while a % $by != 0 {
-            a += 1;
+impl CharSpace {
+    fn num_chars(&self) -> usize {
+        match self {
+            Concrete(s) => s.chars().count(),
+            Intervals(arr) => arr.iter().map(|(a, b)| cp_len!(*a, *b)).sum(),
         }
-        a / $by
-    }};
+    }
 }
 
-impl CharSpace {
-    fn idx_to_char(&self, u: uVar) -> char {
-        match self {
-            Concrete(s) => s.chars().nth(u as usize).unwrap(),
-            Intervals(arr) => {
-                let mut u = u as usize;
-                for (a, b) in *arr {
-                    let crt_len = cp_len!(*a, *b);
-                    if u < crt_len {
-                        return (*a..=*b).nth(u).expect(&format!("is {}", u));
-                    } else {
-                        u -= crt_len;
+impl core::fmt::Debug for Encoding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Encoding")
+            .field("name", &self.name)
+            .field("long_name", &self.long_name)
+            .field("id", &self.id)
+            .field("char_space", &self.char_space)
+            .field("pad_char", &self.pad_char)
+            .field("mode", &self.mode)
+            .field("bit_order", &self.bit_order)
+            .field("tail_bits", &self.tail_bits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl core::str::FromStr for &Encoding {
+    type Err = DecodeError;
+
+    /// Look the name up among the built-in encodings and their aliases, like `Registry::get()`.
+    /// Encodings registered at runtime can only be found through their `Registry`.
+    #[cfg(feature = "std")]
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        registry::Registry::builtin(key)
+    }
+
+    /// Look the name up among the built-in encodings, ignoring ASCII case
+    #[cfg(not(feature = "std"))]
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        let key = key.trim();
+        get_encodings()
+            .iter()
+            .find(|x| x.name.eq_ignore_ascii_case(key))
+            .map(|x| &**x)
+            .ok_or_else(|| DecodeError::UnknownEncoding {
+                name: alloc::borrow::ToOwned::to_owned(key),
+                suggestion: None,
+            })
+    }
+}
+
+impl Encoding {
+    fn new(
+        name: &str,
+        long_name: &str,
+        char_space: CharSpace,
+        pad_char: PadType,
+        mode: Mode,
+    ) -> Encoding {
+        let lookup = Lookup::new(&char_space);
+        let ascii = AsciiLookup::new(&char_space);
+        // the widest and narrowest chars are always at the ends of the intervals
+        let edges = match &char_space {
+            Concrete(s) => s.chars().collect::<Vec<char>>(),
+            Intervals(arr) => arr
+                .iter()
+                .flat_map(|&(a, b)| once(a).chain(once(b)))
+                .collect(),
+        };
+        let char_width = uniform_width(edges.iter().copied());
+        let num_chars = char_space.num_chars();
+        let width_steps = match char_width {
+            Some(_) => vec![],
+            None => width_steps(&char_space),
+        };
+        let max_width = (
+            edges.iter().map(|c| c.len_utf8()).max().unwrap_or(0),
+            edges.iter().map(|c| c.len_utf16()).max().unwrap_or(0),
+        );
+        Encoding {
+            name: name.to_owned(),
+            long_name: long_name.to_owned(),
+            id: None,
+            char_space,
+            num_chars,
+            bitcount: num_chars.max(1).ilog2() as u8,
+            pad_char,
+            mode,
+            bit_order: BitOrder::MsbFirst,
+            tail_bits: None,
+            tails: None,
+            lookup,
+            ascii,
+            char_width,
+            width_steps,
+            max_width,
+        }
+    }
+
+    fn with_id(mut self, id: u16) -> Encoding {
+        self.id = Some(id);
+        self
+    }
+
+    fn with_bit_order(mut self, bit_order: BitOrder) -> Encoding {
+        self.bit_order = bit_order;
+        self
+    }
+
+    fn with_tail_bits(mut self, bits: u8) -> Encoding {
+        let start = 1 << self.bitcount;
+        self.tail_bits = Some(bits);
+        self.tails = Some(start..start + (1 << bits));
+        self
+    }
+
+    pub fn id(&self) -> Option<u16> {
+        self.id
+    }
+
+    /// Order in which repack mode packs bits into chars. Other modes don't pack bits.
+    pub fn bit_order(&self) -> BitOrder {
+        self.bit_order
+    }
+
+    /// Bits held by the tail chars of repack mode, if any (see `EncodingBuilder::tail_bits()`)
+    pub fn tail_bits(&self) -> Option<u8> {
+        self.tail_bits
+    }
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut s = String::new();
+        self.encode_into(bytes, &mut s);
+        s
+    }
+
+    /// Append the encoding of `bytes` to `out`, reserving exactly the space it needs. Nothing else
+    /// is allocated, except for the digits of the number in radix mode.
+    pub fn encode_into(&self, bytes: &[u8], out: &mut String) {
+        let pad_len = self.get_pad_len(bytes.len());
+        match self.mode {
+            Repack => {
+                let len = match self.char_width {
+                    Some(w) => self.body_chars(bytes.len()) * w,
+                    None => {
+                        let mut len = 0;
+                        self.repack_symbols(bytes, |x| len += self.char_len(x));
+                        len
                     }
+                };
+                self.reserve_text(out, len, pad_len);
+                match &self.ascii {
+                    Some(ascii) => self.repack_symbols(bytes, |x| out.push(ascii.idx_to_char(x))),
+                    None => self.repack_symbols(bytes, |x| out.push(self.lookup.idx_to_char(x))),
                 }
-                panic!();
+            }
+            Radix => {
+                let digits = radix::to_radix(bytes, self.num_chars as uVar);
+                let len = self.text_len(digits.len(), digits.iter().copied());
+                self.reserve_text(out, len, pad_len);
+                out.extend(digits.iter().map(|&x| self.lookup.idx_to_char(x)));
+            }
+            BlockRadix => {
+                let base = self.num_chars as uVar;
+                let (block_len, _) = self.block_size();
+                let digits = || {
+                    bytes.chunks(block_len).flat_map(move |x| {
+                        radix::encode_block(x, radix::digits_for(x.len(), base), base)
+                    })
+                };
+                let len = self.text_len(self.body_chars(bytes.len()), digits());
+                self.reserve_text(out, len, pad_len);
+                out.extend(digits().map(|x| self.lookup.idx_to_char(x)));
             }
         }
+        // Append padding chars
+        out.extend(core::iter::repeat_n(*self.pad_char, pad_len));
     }
 
-    fn char_to_idx(&self, c: char) -> Result<uVar, String> {
-        match self {
-            Concrete(s) => s
-                .chars()
-                .position(|x| x == c)
-                .map(|x| x as uVar)
-                .ok_or(format!("Invalid char {}", c)),
-            Intervals(arr) => {
-                let mut crt_idx = 0;
-                for (a, b) in *arr {
-                    if (*a..=*b).contains(&c) {
-                        return Ok((crt_idx + (*a..=*b).position(|x| x == c).unwrap()) as uVar);
-                    } else {
-                        crt_idx += cp_len!(*a, *b);
-                    }
+    /// Call `f` on every repack-mode symbol of the encoding of `bytes`: the bulk a word at a time,
+    /// then the rest symbol by symbol. Words end on symbol and byte boundaries, so they never hold
+    /// a partial last symbol
+    fn repack_symbols<F: FnMut(uVar)>(&self, bytes: &[u8], mut f: F) {
+        let width = self.bitcount();
+        let nchars = self.body_chars(bytes.len());
+        let done = repack::pack_words(bytes, width, self.bit_order, &mut f);
+        let it = bytes[done..].iter().map(|&x| x as uVar);
+        let it = RepackIterator::new(it, 8, width, self.bit_order);
+        let first = done * 8 / width as usize;
+        for (i, x) in it.enumerate() {
+            f(match first + i + 1 == nchars {
+                true => self.last_symbol(x, bytes.len()),
+                false => x,
+            });
+        }
+    }
+
+    /// UTF-8 length of the char at index `u` of the alphabet
+    fn char_len(&self, u: uVar) -> usize {
+        match self.char_width {
+            Some(w) => w,
+            None => {
+                let i = self.width_steps.partition_point(|x| x.0 <= u);
+                self.width_steps[i - 1].1
+            }
+        }
+    }
+
+    /// UTF-8 length of the `nchars` chars of `symbols` (only walked if the alphabet mixes widths)
+    fn text_len<I: Iterator<Item = uVar>>(&self, nchars: usize, symbols: I) -> usize {
+        match self.char_width {
+            Some(w) => nchars * w,
+            None => symbols.map(|x| self.char_len(x)).sum(),
+        }
+    }
+
+    /// Reserve `len` bytes of chars, plus `pad_len` padding chars
+    fn reserve_text(&self, out: &mut String, len: usize, pad_len: usize) {
+        out.reserve_exact(len + pad_len * self.pad_char.len_utf8());
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut acc = vec![];
+        self.decode_into(text, &mut acc)?;
+        Ok(acc)
+    }
+
+    /// Append the decoding of `text` to `out`, reserving exactly the space it needs (in radix
+    /// mode, an upper bound of it). Nothing else is allocated, except for the digits of the number
+    /// in radix mode. On error, `out` is left as it was.
+    pub fn decode_into(&self, text: &str, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let start = out.len();
+        let len = self.max_decoded_len(text);
+        out.reserve_exact(len);
+        out.resize(start + len, 0);
+        match self.decode_segments(text, &mut out[start..]) {
+            Ok(n) => {
+                out.truncate(start + n);
+                Ok(())
+            }
+            Err(e) => {
+                out.truncate(start);
+                Err(e)
+            }
+        }
+    }
+
+    /// Decode `text` to the start of `out`, and return the decoded length. Fails with
+    /// `BufferTooSmall` if `out` can't hold it (in radix mode, if it can't hold an upper bound of
+    /// it). The contents of `out` are unspecified after an error.
+    pub fn decode_to_slice(&self, text: &str, out: &mut [u8]) -> Result<usize, DecodeError> {
+        let needed = self.max_decoded_len(text);
+        if out.len() < needed {
+            return Err(DecodeError::BufferTooSmall { needed });
+        }
+        self.decode_segments(text, out)
+    }
+
+    /// `decode()`, but only accepting the exact output of `encode()`: no concatenated parts, no
+    /// missing or extra padding, and no set bits past the end of the data. Every accepted `text`
+    /// is thus the only encoding of its bytes, i.e. `encode(decode_strict(text)?) == text`.
+    pub fn decode_strict(&self, text: &str) -> Result<Vec<u8>, DecodeError> {
+        let pad = *self.pad_char;
+        let unpadded = text.trim_end_matches(pad);
+        // in repack mode, chars past the first 2^bitcount() would lose their high bits, except for
+        // a last tail char
+        let usable = match self.mode {
+            Repack => 1 << self.bitcount(),
+            _ => uVar::MAX,
+        };
+        let tail = self.last_tail(unpadded);
+        for (char_index, (byte_offset, c)) in unpadded.char_indices().enumerate() {
+            if c == pad {
+                return Err(DecodeError::UnexpectedPad {
+                    char_index,
+                    byte_offset,
+                });
+            }
+            let last = byte_offset + c.len_utf8() == unpadded.len();
+            if self.lookup.char_to_idx(c).is_some_and(|x| x >= usable) && !(last && tail.is_some())
+            {
+                return Err(DecodeError::InvalidChar {
+                    char: c,
+                    char_index,
+                    byte_offset,
+                });
+            }
+        }
+
+        let bytes = self.decode(text)?;
+        let nchars = unpadded.chars().count();
+        let pad_len = (text.len() - unpadded.len()) / pad.len_utf8();
+        // `body_chars()` is only an upper bound in radix mode, where the digits are canonical anyway
+        if pad_len != self.get_pad_len(bytes.len())
+            || (self.mode != Radix && nchars != self.body_chars(bytes.len()))
+        {
+            return Err(DecodeError::InvalidPadding);
+        }
+        if self.mode == Repack {
+            // a tail char ends the text if and only if the bits left over fit in it
+            if tail.is_some() != self.ends_with_tail(bytes.len()) {
+                return Err(DecodeError::InvalidPadding);
+            }
+            // the bits of the last char past the last byte (fewer than it holds)
+            let (width, last) = match tail {
+                Some(t) => (self.tail_bits.unwrap() as usize, t),
+                None => {
+                    let last = unpadded.chars().last();
+                    let last = last.map_or(0, |c| self.lookup.char_to_idx(c).unwrap());
+                    (self.bitcount() as usize, last)
                 }
-                Err(format!("Invalid char {}", c))
+            };
+            let ebc = self.bitcount() as usize;
+            let extra = nchars * ebc + width - ebc - bytes.len() * 8;
+            let mask = self.padding_mask(width, extra);
+            let expected = match self.pad_char {
+                OnesPad(_) => mask,
+                _ => 0,
+            };
+            if last & mask != expected {
+                return Err(DecodeError::TrailingBits);
             }
         }
+        Ok(bytes)
     }
 
-    fn num_chars(&self) -> usize {
-        match self {
-            Concrete(s) => s.chars().count(),
-            Intervals(arr) => arr.iter().map(|(a, b)| cp_len!(*a, *b)).sum(),
+    /// Decoded length of `text`. Exact for valid texts, except in radix mode, where it is an upper
+    /// bound (the leading digits decide the length).
+    pub fn max_decoded_len(&self, text: &str) -> usize {
+        self.deconcat(text)
+            .map(|(_, txt)| {
+                let unpadded = txt.trim_end_matches(*self.pad_char);
+                let nchars = unpadded.chars().count();
+                let pad_len = (txt.len() - unpadded.len()) / self.pad_char.len_utf8();
+                match self.mode {
+                    Repack if self.last_tail(unpadded).is_some() => self.tail_decoded_len(nchars),
+                    Repack => {
+                        let produced = (nchars * self.bitcount() as usize).div_ceil(8);
+                        produced.saturating_sub(self.drop_count(nchars, pad_len))
+                    }
+                    Radix => {
+                        // every leading zero digit is a byte, the rest is at most
+                        // ceil(log2(base)) bits per digit
+                        let zeros = unpadded
+                            .chars()
+                            .take_while(|&c| self.lookup.char_to_idx(c) == Some(0))
+                            .count();
+                        zeros + ((nchars - zeros) * (self.bitcount() as usize + 1)).div_ceil(8)
+                    }
+                    BlockRadix => {
+                        let (block_len, block_chars) = self.block_size();
+                        let base = self.num_chars as uVar;
+                        self.block_tail(nchars, pad_len)
+                            .ok()
+                            .and_then(|tail_len| {
+                                let body = nchars.checked_sub(radix::digits_for(tail_len, base))?;
+                                Some(body / block_chars * block_len + tail_len)
+                            })
+                            .unwrap_or(0)
+                    }
+                }
+            })
+            .sum()
+    }
+
+    /// Decode every concatenated part of `text` into `out`, which must be `max_decoded_len()` long
+    fn decode_segments(&self, text: &str, out: &mut [u8]) -> Result<usize, DecodeError> {
+        let mut written = 0;
+        let mut char_offset = 0;
+        for (byte_offset, txt) in self.deconcat(text) {
+            written += self.decode_single(txt, char_offset, byte_offset, &mut out[written..])?;
+            char_offset += txt.chars().count();
         }
+        Ok(written)
     }
-}
 
-impl std::str::FromStr for &Encoding {
-    type Err = String;
+    /// Length of the encoding of `n_bytes` bytes. The char count is exact, except in radix mode,
+    /// where it's the longest possible (the leading bytes decide the length). The UTF-8 and UTF-16
+    /// lengths are exact only if every char of the alphabet has the same width, and the longest
+    /// possible otherwise. Lengths past `usize::MAX` saturate to it.
+    pub fn encoded_len(&self, n_bytes: usize) -> EncodedLen {
+        let nchars = self.body_chars_wide(n_bytes);
+        let pad_len = self.get_pad_len(n_bytes) as u128;
+        let pad = *self.pad_char;
+        EncodedLen {
+            chars: saturate(nchars + pad_len),
+            utf8: saturate(nchars * self.max_width.0 as u128 + pad_len * pad.len_utf8() as u128),
+            utf16: saturate(nchars * self.max_width.1 as u128 + pad_len * pad.len_utf16() as u128),
+        }
+    }
 
-    fn from_str(key: &str) -> Result<Self, Self::Err> {
-        let key = key.trim().to_lowercase().to_owned();
-        ENCODINGS
-            .iter()
-            .find(|x| x.name == key)
-            .ok_or(format!("invalid encoding: {}", key))
+    /// The most bytes whose encoding fits in `char_budget` chars (see `encoded_len()`)
+    pub fn max_payload_for(&self, char_budget: usize) -> usize {
+        let fits = |n: usize| self.total_chars(n) <= char_budget as u128;
+        // the longest payload whose chars (without padding) fit, and how much shorter a payload
+        // can have to be to leave room for the padding. The length isn't monotonic with `DropPad`
+        // (e.g. 2 bytes of kanji need 1 char, 1 byte needs 2), so every length in between is
+        // checked, not just until the first that fits
+        let (upper, window) = match self.mode {
+            Repack => {
+                // whole blocks of `lcm(bitcount(), 8)` bits never get padding
+                let ebc = self.bitcount() as usize;
+                let upper = saturate(char_budget as u128 * ebc as u128 / 8);
+                (upper, lcm(ebc, 8) / 8)
+            }
+            BlockRadix => {
+                // whole blocks never get padding, and a short one is shorter than a block
+                let (block_len, block_chars) = self.block_size();
+                let nblocks = (char_budget / block_chars) as u128;
+                let upper = saturate(nblocks * block_len as u128 + block_len as u128 - 1);
+                (upper, block_len)
+            }
+            Radix => {
+                // the length only grows with the payload, which takes at least a char per byte
+                let (mut lo, mut hi) = (0, char_budget);
+                while lo < hi {
+                    let mid = hi - (hi - lo) / 2;
+                    match fits(mid) {
+                        true => lo = mid,
+                        false => hi = mid - 1,
+                    }
+                }
+                return lo;
+            }
+        };
+        (upper.saturating_sub(window)..=upper)
+            .rev()
+            .find(|&n| fits(n))
+            .unwrap_or(0)
     }
-}
 
-impl Encoding {
-    pub fn encode(&self, bytes: &[u8]) -> String {
-        let it = bytes.iter().map(|&x| x as uVar);
-        let it = RepackIterator::new(it, 8, self.bitcount());
-        let mut s = it
-            .map(|x| self.char_space.idx_to_char(x))
-            .collect::<String>();
-        // Append padding chars
-        let pad_len = self.get_pad_len(bytes);
-        s += &self.pad_char.to_string().repeat(pad_len);
-        s
+    /// How many chars (without padding) encode `n_bytes` bytes? The most possible, in radix mode.
+    /// Saturates to `usize::MAX`.
+    fn body_chars(&self, n_bytes: usize) -> usize {
+        saturate(self.body_chars_wide(n_bytes))
     }
 
-    pub fn decode(&self, text: &str) -> Result<Vec<u8>, String> {
-        let mut acc = vec![];
-        for txt in self.deconcat(text) {
-            acc.append(&mut self.decode_single(txt)?);
+    /// `body_chars()`, without saturating
+    fn body_chars_wide(&self, n_bytes: usize) -> u128 {
+        let base = self.num_chars as uVar;
+        match self.mode {
+            Repack => (n_bytes as u128 * 8).div_ceil(self.bitcount() as u128),
+            // leading zero bytes are a digit each, the rest is at most `digits_for()` long
+            Radix if n_bytes == 0 => 0,
+            Radix => core::cmp::max(n_bytes as u128, radix::digits_for_wide(n_bytes, base)),
+            BlockRadix => {
+                let (block_len, block_chars) = self.block_size();
+                let tail_chars = match n_bytes % block_len {
+                    0 => 0,
+                    tail_len => radix::digits_for(tail_len, base),
+                };
+                let nblocks = (n_bytes / block_len) as u128;
+                nblocks * block_chars as u128 + tail_chars as u128
+            }
         }
-        Ok(acc)
     }
 
+    /// Length in chars of the encoding of `n_bytes` bytes, padding included, without saturating
+    fn total_chars(&self, n_bytes: usize) -> u128 {
+        self.body_chars_wide(n_bytes) + self.get_pad_len(n_bytes) as u128
+    }
+
+    /// Bits held by a repack-mode char: only the first `2^bitcount()` chars of the alphabet hold
+    /// data
     pub fn bitcount(&self) -> u8 {
-        let l = self.char_space.num_chars();
-        let mut i = 0;
-        // 00011010
-        loop {
-            if 1 << i + 1 > l {
-                break i;
+        self.bitcount
+    }
+
+    /// The `(bytes, chars)` block used by the block-radix mode
+    fn block_size(&self) -> (usize, usize) {
+        let base = self.num_chars as uVar;
+        radix::best_block(base, !matches!(self.pad_char, DropPad(_)))
+    }
+
+    /// The smallest `(bytes, chars)` unit that encodes independently of its neighbours. Radix mode
+    /// has none, the whole input is a single unit.
+    #[cfg(feature = "std")]
+    fn stream_block(&self) -> Option<(usize, usize)> {
+        match self.mode {
+            Repack => {
+                let ebc = self.bitcount() as usize;
+                let block_len = lcm(ebc, 8);
+                Some((block_len / 8, block_len / ebc))
             }
-            i += 1;
+            Radix => None,
+            BlockRadix => Some(self.block_size()),
         }
     }
 
     /// How many padding chars should be added to the encoding?
-    fn get_pad_len(&self, arr: &[u8]) -> usize {
+    fn get_pad_len(&self, nbytes: usize) -> usize {
         let ebc = self.bitcount() as usize;
-        let nbytes = arr.len();
-        if nbytes == 0 {
+        if nbytes == 0 || matches!(self.pad_char, OmitPad(_) | OnesPad(_)) {
             return 0;
         }
+        if self.mode == Radix {
+            return 1;
+        }
+        if self.mode == BlockRadix {
+            let (block_len, block_chars) = self.block_size();
+            let tail_len = nbytes % block_len;
+            return match self.pad_char {
+                _ if tail_len == 0 => 0,
+                BlockPad(_) | OmitPad(_) | OnesPad(_) => {
+                    let base = self.num_chars as uVar;
+                    block_chars - radix::digits_for(tail_len, base)
+                }
+                DropPad(_) => block_len - tail_len,
+            };
+        }
         match self.pad_char {
-            BlockPad(_) => {
+            BlockPad(_) | OmitPad(_) | OnesPad(_) => {
                 let block_len = lcm(ebc, 8);
-                let last_bits = (nbytes * 8) % block_len;
+                // block_len is a multiple of 8, so this is `nbytes * 8 % block_len`
+                let last_bits = nbytes % (block_len / 8) * 8;
                 if last_bits == 0 {
                     return 0;
                 }
                 (block_len - last_bits) / ebc
             }
             DropPad(_) => {
-                let inp_bitlen = nbytes * 8;
-                let out_bitlen = div_ceil!(inp_bitlen, ebc) * ebc;
-                let extra = out_bitlen - inp_bitlen;
-                div_ceil!(extra, 8)
+                // the bits of the last char past the data
+                let extra = (ebc - nbytes % ebc * 8 % ebc) % ebc;
+                extra.div_ceil(8)
             }
         }
     }
 
-    /// Separate a concatenated encoding into its individual parts
-    fn deconcat<'a>(&self, text: &'a str) -> Vec<&'a str> {
-        let mut acc = vec![];
-        let mut prev_i = 0;
-        let mut met = false;
-        for (i, c) in text.char_indices() {
-            if met == false && c == *self.pad_char {
-                met = true;
+    /// Separate a concatenated encoding into its individual parts, along with their byte offsets.
+    /// Parts end with padding, or with a tail char (and its padding).
+    fn deconcat<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+        let pad = *self.pad_char;
+        let mut rest = Some((0, text));
+        core::iter::from_fn(move || {
+            let (offset, txt) = rest?;
+            let mut met = false;
+            for (i, c) in txt.char_indices() {
+                if c == pad {
+                    met = true;
+                } else if met {
+                    rest = Some((offset + i, &txt[i..]));
+                    return Some((offset, &txt[..i]));
+                } else if self.tail_value(c).is_some() {
+                    met = true;
+                }
             }
-            if met == true && c != *self.pad_char {
-                acc.push(text.get(prev_i..i).unwrap());
-                met = false;
-                prev_i = i;
+            rest = None;
+            Some((offset, txt))
+        })
+    }
+
+    /// How many of the bytes repacked from `nchars` chars are not part of the data?
+    fn drop_count(&self, nchars: usize, pad_len: usize) -> usize {
+        match self.pad_char {
+            BlockPad(_) | OmitPad(_) | OnesPad(_) => {
+                let block_char_size = lcm(8, self.bitcount() as usize) / self.bitcount() as usize;
+                core::cmp::min(1, nchars % block_char_size)
+            }
+            DropPad(_) => pad_len,
+        }
+    }
+
+    /// The most bytes `DropPad` padding can drop in repack mode: those holding the bits of the last
+    /// char past the data, i.e. fewer than `bitcount()` bits
+    fn max_drop_pad(&self) -> usize {
+        (self.bitcount() as usize - 1).div_ceil(8)
+    }
+
+    /// Does the repack-mode encoding of `nbytes` bytes end with a tail char, i.e. do the bits left
+    /// over by the other chars fit in one?
+    fn ends_with_tail(&self, nbytes: usize) -> bool {
+        let left = nbytes * 8 % self.bitcount() as usize;
+        self.tail_bits
+            .is_some_and(|x| left != 0 && left <= x as usize)
+    }
+
+    /// The last repack-mode symbol `x` of the encoding of `nbytes` bytes, as a tail char if needed,
+    /// and with its bits past the data set for `OnesPad`
+    fn last_symbol(&self, x: uVar, nbytes: usize) -> uVar {
+        let width = self.bitcount() as usize;
+        let left = nbytes * 8 % width;
+        if left == 0 {
+            return x;
+        }
+        let tail = self.ends_with_tail(nbytes);
+        // `RepackIterator` filled `x` up to `bitcount()` bits with zeros, keep `tail_bits` of them
+        let (bits, x) = match (tail, self.bit_order) {
+            (false, _) => (width, x),
+            (true, BitOrder::MsbFirst) => {
+                let bits = self.tail_bits.unwrap() as usize;
+                (bits, x >> (width - bits))
             }
+            (true, BitOrder::LsbFirst) => (self.tail_bits.unwrap() as usize, x),
+        };
+        let x = match self.pad_char {
+            OnesPad(_) => x | self.padding_mask(bits, bits - left),
+            _ => x,
+        };
+        match tail {
+            true => (1 << width) + x,
+            false => x,
         }
-        acc.push(text.get(prev_i..).unwrap());
-        acc
     }
 
-    /// Decode a non-concatenated string
-    fn decode_single(&self, text: &str) -> Result<Vec<u8>, String> {
+    /// Where the `extra` bits past the data are, in a last symbol of `width` bits
+    fn padding_mask(&self, width: usize, extra: usize) -> uVar {
+        let ones = (1u64 << extra) - 1;
+        let ones = match self.bit_order {
+            BitOrder::MsbFirst => ones,
+            BitOrder::LsbFirst => ones << (width - extra),
+        };
+        ones as uVar
+    }
+
+    /// The bits held by `c`, if it's a tail char
+    fn tail_value(&self, c: char) -> Option<uVar> {
+        // most encodings have no tail chars, and can skip the lookup
+        self.tails.as_ref()?;
+        self.tail_of(self.lookup.char_to_idx(c)?)
+    }
+
+    /// `tail_value()`, for the char at index `u` of the alphabet
+    fn tail_of(&self, u: uVar) -> Option<uVar> {
+        let tails = self.tails.as_ref()?;
+        tails.contains(&u).then(|| u - tails.start)
+    }
+
+    /// The bits held by the last char of `text`, if it's a tail char
+    fn last_tail(&self, text: &str) -> Option<uVar> {
+        self.tail_value(text.chars().next_back()?)
+    }
+
+    /// How many bytes a repack-mode part of `nchars` chars holds when it ends with a tail char.
+    /// Unlike other chars, its bits past the last byte never make up a whole byte.
+    fn tail_decoded_len(&self, nchars: usize) -> usize {
+        let bits = self.tail_bits.unwrap_or(0) as usize;
+        ((nchars - 1) * self.bitcount() as usize + bits) / 8
+    }
+
+    /// Decode a non-concatenated string, found at the given offsets of the whole input, into
+    /// `out`. Returns the decoded length.
+    fn decode_single(
+        &self,
+        text: &str,
+        char_offset: usize,
+        byte_offset: usize,
+        out: &mut [u8],
+    ) -> Result<usize, DecodeError> {
         let unpadded = text.trim_end_matches(*self.pad_char);
+        let nchars = unpadded.chars().count();
+        let pad_len = (text.len() - unpadded.len()) / self.pad_char.len_utf8();
+        // `deconcat()` leaves tail chars last
+        let tail = self.last_tail(unpadded);
+
+        // the bulk of repack-mode texts goes a word at a time
+        let (skip_bytes, skip_chars, written) = match self.mode {
+            Repack => self.decode_words(unpadded, out),
+            _ => (0, 0, 0),
+        };
 
         // decode to array of bytes
-        let mut err = Ok(vec![0]);
-        let it = unpadded
-            .chars()
+        let mut err = Ok(());
+        let mut it = unpadded[skip_bytes..]
+            .char_indices()
             .enumerate()
-            .map(|(i, x)| (i, self.char_space.char_to_idx(x)))
-            .scan(0, |_, (i, x)| match x {
+            .map(|(i, (j, x))| {
+                let u = self.lookup.char_to_idx(x).ok_or(DecodeError::InvalidChar {
+                    char: x,
+                    char_index: char_offset + skip_chars + i,
+                    byte_offset: byte_offset + skip_bytes + j,
+                })?;
+                // back to a `bitcount()`-bit symbol, filled up with zeros
+                Ok(match tail {
+                    Some(t) if skip_chars + i + 1 == nchars => match self.bit_order {
+                        BitOrder::MsbFirst => t << (self.bitcount() - self.tail_bits.unwrap()),
+                        BitOrder::LsbFirst => t,
+                    },
+                    _ => u,
+                })
+            })
+            .scan((), |_, x| match x {
                 Ok(o) => Some(o),
                 Err(e) => {
-                    err = Err(format!("Error: At char #{}: {}", i, e));
+                    err = Err(e);
                     None
                 }
             });
-        let mut arr = RepackIterator::new(it, self.bitcount(), 8)
-            .map(|x| x as u8)
-            .collect::<Vec<u8>>();
-        err?;
-
-        // drop extra bytes resulted from the decoding, if any
-        let drop_count = match self.pad_char {
-            BlockPad(_) => {
-                let block_char_size = lcm(8, self.bitcount() as usize) / self.bitcount() as usize;
-                std::cmp::min(1, unpadded.chars().count() % block_char_size)
+        match self.mode {
+            Repack => {
+                // the bytes that don't fit in `out` are the ones dropped below
+                let it = RepackIterator::new(it, self.bitcount(), 8, self.bit_order);
+                for (i, x) in it.enumerate() {
+                    if let Some(b) = out.get_mut(written + i) {
+                        *b = x as u8;
+                    }
+                }
+                err?;
+
+                if tail.is_some() {
+                    return Ok(self.tail_decoded_len(nchars));
+                }
+                if matches!(self.pad_char, DropPad(_)) && pad_len > self.max_drop_pad() {
+                    return Err(DecodeError::InvalidPadding);
+                }
+                // drop extra bytes resulted from the decoding, if any
+                let produced = (nchars * self.bitcount() as usize).div_ceil(8);
+                produced
+                    .checked_sub(self.drop_count(nchars, pad_len))
+                    .ok_or(DecodeError::InvalidPadding)
+            }
+            Radix => {
+                let digits = it.collect::<Vec<uVar>>();
+                err?;
+                let arr = radix::from_radix(&digits, self.num_chars as uVar);
+                out[..arr.len()].copy_from_slice(&arr);
+                Ok(arr.len())
             }
-            DropPad(_) => text.chars().count() - unpadded.chars().count(),
+            BlockRadix => {
+                let res = self.decode_blocks(&mut it, nchars, pad_len, char_offset, out);
+                // invalid chars take precedence over invalid blocks
+                it.for_each(drop);
+                err?;
+                res
+            }
+        }
+    }
+
+    /// Decode the bulk of a repack-mode `text` a word at a time (see `repack::unpack_words()`), up
+    /// to its first invalid char. Returns the count of bytes and of chars of `text` it consumed,
+    /// and of bytes written to `out`; the rest decodes the same on its own.
+    fn decode_words(&self, text: &str, out: &mut [u8]) -> (usize, usize, usize) {
+        // symbols translated at once, a multiple of the symbols of every word
+        const CHUNK: usize = 64;
+        let width = self.bitcount();
+        if repack::word_bits(width).is_none() {
+            return (0, 0, 0);
+        }
+        // tail chars are left to `decode_single()`
+        let usable = match self.tail_bits {
+            Some(_) => 1 << width,
+            None => uVar::MAX,
         };
-        arr.resize(arr.len() - drop_count, 0);
+        let (mut nbytes, mut nchars, mut written) = (0, 0, 0);
+        match &self.ascii {
+            Some(ascii) => {
+                let mut buf = [0u8; CHUNK];
+                for chunk in text.as_bytes().chunks(CHUNK) {
+                    let mut n = ascii.translate(chunk, &mut buf);
+                    if let Some(i) = buf[..n].iter().position(|&x| x as uVar >= usable) {
+                        n = i;
+                    }
+                    let (syms, len) =
+                        repack::unpack_words(&buf[..n], width, self.bit_order, &mut out[written..]);
+                    nchars += syms;
+                    written += len;
+                    if syms < chunk.len() {
+                        break;
+                    }
+                }
+                nbytes = nchars;
+            }
+            None => {
+                let mut buf = [0 as uVar; CHUNK];
+                // where every char of `buf` ends in `text`
+                let mut ends = [0; CHUNK];
+                let mut chars = text.char_indices();
+                loop {
+                    let mut n = 0;
+                    while n < CHUNK {
+                        let (i, c) = match chars.next() {
+                            Some(x) => x,
+                            None => break,
+                        };
+                        match self.lookup.char_to_idx(c) {
+                            Some(u) if u < usable => buf[n] = u,
+                            _ => break,
+                        }
+                        ends[n] = i + c.len_utf8();
+                        n += 1;
+                    }
+                    let (syms, len) =
+                        repack::unpack_words(&buf[..n], width, self.bit_order, &mut out[written..]);
+                    if syms > 0 {
+                        nbytes = ends[syms - 1];
+                    }
+                    nchars += syms;
+                    written += len;
+                    if syms < CHUNK {
+                        break;
+                    }
+                }
+            }
+        }
+        (nbytes, nchars, written)
+    }
 
-        Ok(arr)
+    /// Length of the short block at the end of a block-radix string, if any
+    fn block_tail(&self, nchars: usize, pad_len: usize) -> Result<usize, DecodeError> {
+        let base = self.num_chars as uVar;
+        let (block_len, block_chars) = self.block_size();
+        let tail_chars = nchars % block_chars;
+        match self.pad_char {
+            BlockPad(_) | OmitPad(_) | OnesPad(_) if tail_chars == 0 => Ok(0),
+            BlockPad(_) | OmitPad(_) | OnesPad(_) => (1..block_len)
+                .find(|&x| radix::digits_for(x, base) == tail_chars)
+                .ok_or(DecodeError::InvalidPadding),
+            DropPad(_) if pad_len == 0 => Ok(0),
+            DropPad(_) if pad_len < block_len => Ok(block_len - pad_len),
+            DropPad(_) => Err(DecodeError::InvalidPadding),
+        }
+    }
+
+    /// Decode the `nchars` digits of a non-concatenated block-radix string into `out`. Returns the
+    /// decoded length.
+    fn decode_blocks<I: Iterator<Item = uVar>>(
+        &self,
+        digits: &mut I,
+        nchars: usize,
+        pad_len: usize,
+        char_offset: usize,
+        out: &mut [u8],
+    ) -> Result<usize, DecodeError> {
+        let base = self.num_chars as uVar;
+        let (block_len, block_chars) = self.block_size();
+
+        let tail_len = self.block_tail(nchars, pad_len)?;
+        let tail_chars = radix::digits_for(tail_len, base);
+        if nchars < tail_chars || !(nchars - tail_chars).is_multiple_of(block_chars) {
+            return Err(DecodeError::InvalidPadding);
+        }
+
+        let nblocks = (nchars - tail_chars) / block_chars;
+        let mut buf = [0; radix::MAX_BLOCK_DIGITS];
+        let mut written = 0;
+        for i in 0..=nblocks {
+            let (ndigits, len) = match i {
+                _ if i < nblocks => (block_chars, block_len),
+                _ => (tail_chars, tail_len),
+            };
+            for d in buf[..ndigits].iter_mut() {
+                *d = digits.next().unwrap_or(0);
+            }
+            let n = radix::decode_block(&buf[..ndigits], len, base).ok_or(
+                DecodeError::InvalidBlock {
+                    char_index: char_offset + i * block_chars,
+                },
+            )?;
+            for (j, b) in out[written..written + len].iter_mut().enumerate() {
+                *b = (n >> (8 * (len - 1 - j))) as u8;
+            }
+            written += len;
+        }
+        Ok(written)
+    }
+}
+
+/// The UTF-8 length of the chars, if they all have the same
+fn uniform_width<I: Iterator<Item = char>>(mut chars: I) -> Option<usize> {
+    let width = chars.next()?.len_utf8();
+    chars.all(|c| c.len_utf8() == width).then_some(width)
+}
+
+/// See `Encoding::width_steps`
+fn width_steps(char_space: &CharSpace) -> Vec<(uVar, usize)> {
+    // the last code point of every UTF-8 length
+    const LAST: [u32; 4] = [0x7F, 0x7FF, 0xFFFF, 0x10FFFF];
+    let mut acc: Vec<(uVar, usize)> = vec![];
+    let mut push = |idx, len| {
+        if acc.last().map(|x| x.1) != Some(len) {
+            acc.push((idx, len));
+        }
+    };
+    match char_space {
+        Concrete(s) => {
+            for (i, c) in s.chars().enumerate() {
+                push(i as uVar, c.len_utf8());
+            }
+        }
+        Intervals(arr) => {
+            let mut idx = 0;
+            for &(a, b) in arr {
+                // split the interval where the length changes
+                let mut c = a as u32;
+                for (len, &last) in (1..).zip(LAST.iter()) {
+                    if c <= last && c <= b as u32 {
+                        push(idx, len);
+                        let end = core::cmp::min(last, b as u32);
+                        idx += end - c + 1;
+                        c = end + 1;
+                    }
+                }
+            }
+        }
     }
+    acc
+}
+
+/// `x`, or `usize::MAX` if it doesn't fit
+fn saturate(x: u128) -> usize {
+    usize::try_from(x).unwrap_or(usize::MAX)
 }
 
 /// smallest common multiple
 fn lcm(a: usize, b: usize) -> usize {
-    let mut crt = std::cmp::max(a, b);
+    let mut crt = core::cmp::max(a, b);
     loop {
-        if crt % a == 0 && crt % b == 0 {
+        if crt.is_multiple_of(a) && crt.is_multiple_of(b) {
             break crt;
         }
         crt += 1;
     }
 }
 
-pub fn get_encodings<'a>() -> &'static [Encoding] {
-    ENCODINGS
+pub fn get_encodings() -> &'static [Arc<Encoding>] {
+    config::encodings()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Encoding;
+    use super::{get_encodings, EncodedLen, Encoding, Mode};
+    use crate::error::DecodeError;
+    use crate::repack::{uVar, BitOrder, RepackIterator};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
 
     #[test]
     fn test_bitcounts() {
@@ -258,6 +1062,12 @@ mod tests {
             let enc = k.parse::<&Encoding>().unwrap();
             assert_eq!(*v, enc.bitcount());
         }
+        // cached when the encoding is built
+        for c in get_encodings() {
+            let len = c.char_space.num_chars();
+            assert_eq!(len, c.num_chars, "Failed with encoding `{}`", c.name);
+            assert!(1 << c.bitcount() <= len && len < 2 << c.bitcount());
+        }
     }
 
     #[test]
@@ -298,4 +1108,751 @@ mod tests {
             assert_eq!(dec.as_bytes(), res, "Decoding mismatch");
         }
     }
+
+    #[test]
+    fn test_drop_pad_limit() {
+        // padding only drops the bytes of the bits past the data (15 for kanji, so 2 bytes). Longer
+        // runs used to be accepted, up to the bytes of the whole part
+        let codec = "kanji".parse::<&Encoding>().unwrap();
+        assert_eq!(2, codec.max_drop_pad());
+        assert_eq!(Ok(vec![]), codec.decode("𠖫々々"));
+        assert_eq!(Err(DecodeError::InvalidPadding), codec.decode("𠖫𠖫々々々"));
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            codec.decode("𠖫𠖫々々々𠖫")
+        );
+        let codec = "base64".parse::<&Encoding>().unwrap();
+        assert_eq!(Ok(b"a".to_vec()), codec.decode("YQ=="));
+    }
+
+    #[test]
+    fn test_radix() {
+        let pairs: &[(&[u8], &str)] = &[
+            (&[0], "一々"),
+            (&[0, 0, 1], "一一丁々"),
+            (&[1, 0], "伀々"),
+            (&[0, 1, 0], "一伀々"),
+            (&[0xff, 0xff, 0xff], "\u{4eee}\u{2437f}々"),
+        ];
+        let codec = "kanji-radix".parse::<&Encoding>().unwrap();
+        for (dec, enc) in pairs {
+            assert_eq!(*enc, codec.encode(dec), "Encoding mismatch");
+            let res = codec.decode(enc).unwrap();
+            assert_eq!(*dec, res, "Decoding mismatch");
+        }
+    }
+
+    #[test]
+    fn test_block_radix() {
+        let pairs = &[
+            ("a" /*     */, "걡흐흐흐흐"),
+            ("aa" /*    */, "갂뛁흐흐흐"),
+            ("aaa" /*   */, "긿쒱흐흐"),
+            ("aaaa" /*  */, "갍뢁픑흐"),
+            ("aaaaa" /* */, "륉퐢쯁"),
+            ("aaaaaa" /**/, "륉퐢쯁걡흐흐흐흐"),
+        ];
+        let codec = "hangul-block".parse::<&Encoding>().unwrap();
+        assert_eq!((5, 3), codec.block_size());
+        for (dec, enc) in pairs {
+            assert_eq!(*enc, codec.encode(dec.as_bytes()), "Encoding mismatch");
+            let res = codec.decode(enc).unwrap();
+            assert_eq!(dec.as_bytes(), res, "Decoding mismatch");
+        }
+
+        // out of range blocks, and final blocks that don't match the padding
+        for enc in &["흏흏흏", "걡흐흐흐", "걡걡흐흐흐흐", "걡걡"] {
+            assert!(codec.decode(enc).is_err(), "`{}` should not decode", enc);
+        }
+    }
+
+    #[test]
+    fn test_best_block() {
+        use crate::radix::best_block;
+        assert_eq!((5, 3), best_block(11088, false));
+        assert_eq!((1, 1), best_block(11088, true));
+        assert_eq!((3, 4), best_block(64, true));
+        assert_eq!((4, 5), best_block(85, false));
+        assert_eq!((1, 8), best_block(2, true));
+    }
+
+    #[test]
+    fn test_log2_fixed() {
+        use crate::radix::{digits_for, log2_fixed, to_radix, LOG2_FRAC_BITS};
+        assert_eq!(6 << LOG2_FRAC_BITS, log2_fixed(64));
+        assert_eq!(1 << LOG2_FRAC_BITS, log2_fixed(2));
+        for &base in &[3, 10, 85, 11088, 70304] {
+            let approx = log2_fixed(base) as f64 / (1u128 << LOG2_FRAC_BITS) as f64;
+            assert!((approx - (base as f64).log2()).abs() < 1e-12, "{}", base);
+            // the digits of the largest number of each length
+            for n in 1..40 {
+                let digits = to_radix(&vec![0xff; n], base).len();
+                assert_eq!(digits, digits_for(n, base), "{} bytes, base {}", n, base);
+            }
+        }
+    }
+
+    #[test]
+    fn test_char_len() {
+        for c in get_encodings() {
+            for u in 0..c.num_chars as uVar {
+                let len = c.lookup.idx_to_char(u).len_utf8();
+                assert_eq!(len, c.char_len(u), "Failed with `{}`", c.name);
+            }
+        }
+        let codec = "kanji".parse::<&Encoding>().unwrap();
+        assert_eq!(None, codec.char_width);
+        assert_eq!(2, codec.width_steps.len());
+    }
+
+    #[test]
+    fn test_into_apis() {
+        for c in get_encodings() {
+            for n in 0..20 {
+                let data = (0..n as u8)
+                    .map(|x| x.wrapping_mul(37))
+                    .collect::<Vec<u8>>();
+                let expected = c.encode(&data);
+
+                let mut s = String::new();
+                c.encode_into(&data, &mut s);
+                assert_eq!(expected, s, "Failed with `{}`", c.name);
+                assert_eq!(s.len(), s.capacity(), "Failed with `{}`", c.name);
+                let mut s = "prefix".to_owned();
+                c.encode_into(&data, &mut s);
+                assert_eq!(format!("prefix{}", expected), s, "Failed with `{}`", c.name);
+
+                let mut v = vec![];
+                c.decode_into(&expected, &mut v).unwrap();
+                assert_eq!(data, v, "Failed with `{}`", c.name);
+                if c.mode != Mode::Radix {
+                    assert_eq!(v.len(), v.capacity(), "Failed with `{}`", c.name);
+                }
+                let mut v = vec![42];
+                c.decode_into(&expected, &mut v).unwrap();
+                assert_eq!(data, v[1..], "Failed with `{}`", c.name);
+
+                let mut buf = [0; 64];
+                let len = c.decode_to_slice(&expected, &mut buf).unwrap();
+                assert_eq!(data, buf[..len], "Failed with `{}`", c.name);
+            }
+        }
+
+        let codec = "base64".parse::<&Encoding>().unwrap();
+        let mut v = vec![1, 2];
+        assert!(codec.decode_into("YQ==YW-h", &mut v).is_err());
+        assert_eq!(vec![1, 2], v);
+        assert_eq!(
+            Err(DecodeError::BufferTooSmall { needed: 4 }),
+            codec.decode_to_slice("YQ==YWFh", &mut [0; 3])
+        );
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        let cases = &[
+            ("base64", 4, (8, 8, 8)),
+            ("hex", 3, (6, 6, 6)),
+            ("kanji", 1, (2, 7, 3)),
+            ("kanji", 2, (1, 4, 2)),
+            ("hangul-block", 6, (8, 24, 8)),
+        ];
+        for &(name, n, (chars, utf8, utf16)) in cases {
+            let codec = name.parse::<&Encoding>().unwrap();
+            let len = EncodedLen { chars, utf8, utf16 };
+            assert_eq!(len, codec.encoded_len(n), "Failed with `{}`", name);
+        }
+        // lengths past usize::MAX saturate, instead of overflowing
+        let max = EncodedLen {
+            chars: usize::MAX,
+            utf8: usize::MAX,
+            utf16: usize::MAX,
+        };
+        let binary = "binary".parse::<&Encoding>().unwrap();
+        assert_eq!(max, binary.encoded_len(usize::MAX / 4));
+        let hex = "hex".parse::<&Encoding>().unwrap();
+        assert_eq!(usize::MAX / 4 * 2, hex.encoded_len(usize::MAX / 4).chars);
+        for c in get_encodings() {
+            for n in [usize::MAX / 4, usize::MAX / 2 + 1, usize::MAX] {
+                let len = c.encoded_len(n);
+                assert!(len.chars >= n / 4, "Failed with `{}`", c.name);
+                assert!(len.utf8 >= len.chars, "Failed with `{}`", c.name);
+            }
+        }
+        let codec = "kanji".parse::<&Encoding>().unwrap();
+        assert_eq!(2, codec.max_payload_for(1));
+        assert_eq!(4, codec.max_payload_for(2));
+        assert_eq!(6, codec.max_payload_for(3));
+        assert_eq!(0, codec.max_payload_for(0));
+    }
+
+    #[test]
+    fn test_max_payload_for_large() {
+        // without scanning every length up to the answer
+        for c in get_encodings() {
+            for budget in [1 << 20, 1 << 30, usize::MAX / 2, usize::MAX] {
+                let n = c.max_payload_for(budget);
+                assert!(c.encoded_len(n).chars <= budget, "Failed with `{}`", c.name);
+                for m in n.saturating_add(1)..n.saturating_add(32) {
+                    assert!(
+                        c.total_chars(m) > budget as u128,
+                        "Failed with `{}`",
+                        c.name
+                    );
+                }
+            }
+        }
+        let binary = "binary".parse::<&Encoding>().unwrap();
+        assert_eq!(usize::MAX / 8, binary.max_payload_for(usize::MAX));
+        let kanji = "kanji-radix".parse::<&Encoding>().unwrap();
+        assert_eq!((1 << 30) - 1, kanji.max_payload_for(1 << 30));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_encoded_len(data in vec(any::<u8>(), 0..64)) {
+            for c in get_encodings() {
+                let enc = c.encode(&data);
+                let len = c.encoded_len(data.len());
+                let real = EncodedLen {
+                    chars: enc.chars().count(),
+                    utf8: enc.len(),
+                    utf16: enc.encode_utf16().count(),
+                };
+                if c.mode == Mode::Radix {
+                    prop_assert!(real.chars <= len.chars, "Failed with `{}`", c.name);
+                    prop_assert!(data.len() <= c.max_decoded_len(&enc), "Failed with `{}`", c.name);
+                } else {
+                    prop_assert_eq!(real.chars, len.chars, "Failed with `{}`", c.name);
+                    prop_assert_eq!(data.len(), c.max_decoded_len(&enc), "Failed with `{}`", c.name);
+                }
+                if c.mode != Mode::Radix && c.char_width.is_some() {
+                    prop_assert_eq!(real, len, "Failed with `{}`", c.name);
+                } else {
+                    prop_assert!(real.utf8 <= len.utf8, "Failed with `{}`", c.name);
+                    prop_assert!(real.utf16 <= len.utf16, "Failed with `{}`", c.name);
+                }
+            }
+        }
+
+        #[test]
+        fn prop_max_payload_for(budget in 0usize..300) {
+            for c in get_encodings() {
+                let n = c.max_payload_for(budget);
+                prop_assert!(c.encoded_len(n).chars <= budget, "Failed with `{}`", c.name);
+                let data = vec![0xff; n];
+                prop_assert!(c.encode(&data).chars().count() <= budget, "Failed with `{}`", c.name);
+                for m in n + 1..n + 32 {
+                    prop_assert!(c.encoded_len(m).chars > budget, "Failed with `{}`", c.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_strict() {
+        let cases = &[
+            ("base64", "YR==", DecodeError::TrailingBits),
+            ("base64", "YQ=", DecodeError::InvalidPadding),
+            ("base64", "YQ===", DecodeError::InvalidPadding),
+            ("base64", "YWFhYQ", DecodeError::InvalidPadding),
+            ("kanji", "𠕋々", DecodeError::TrailingBits),
+            ("kanji", "𠖫々", DecodeError::TrailingBits),
+            ("kanji", "𠖫𠕊々々", DecodeError::InvalidPadding),
+            ("kanji-radix", "一", DecodeError::InvalidPadding),
+            ("kanji-radix", "一々々", DecodeError::InvalidPadding),
+            ("hangul-block", "걡흐흐흐", DecodeError::InvalidPadding),
+        ];
+        for (name, text, err) in cases {
+            let codec = name.parse::<&Encoding>().unwrap();
+            assert_eq!(Err(err.clone()), codec.decode_strict(text), "`{}`", text);
+        }
+
+        let codec = "base64".parse::<&Encoding>().unwrap();
+        assert_eq!(
+            Err(DecodeError::UnexpectedPad {
+                char_index: 2,
+                byte_offset: 2,
+            }),
+            codec.decode_strict("YQ==YQ==")
+        );
+        assert_eq!(b"a".to_vec(), codec.decode_strict("YQ==").unwrap());
+        let codec = "kanji".parse::<&Encoding>().unwrap();
+        assert_eq!(
+            Err(DecodeError::InvalidChar {
+                char: '\u{2a6df}',
+                char_index: 1,
+                byte_offset: 4,
+            }),
+            codec.decode_strict("𠖫\u{2a6df}")
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_decode_strict(
+            data in vec(any::<u8>(), 0..32),
+            idxs in vec((any::<u32>(), 0..8u8), 0..12),
+        ) {
+            for c in get_encodings() {
+                // every encoding is accepted
+                let enc = c.encode(&data);
+                prop_assert_eq!(&data, &c.decode_strict(&enc).unwrap(), "Failed with `{}`", c.name);
+
+                // every accepted text is an encoding
+                let base = c.char_space.num_chars() as u32;
+                let text = idxs
+                    .iter()
+                    .map(|&(i, p)| match p {
+                        0 => *c.pad_char,
+                        _ => c.lookup.idx_to_char(i % base),
+                    })
+                    .collect::<String>();
+                if let Ok(dec) = c.decode_strict(&text) {
+                    prop_assert_eq!(text, c.encode(&dec), "Failed with `{}`", c.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let codec = "base64".parse::<&Encoding>().unwrap();
+        // positions are relative to the whole input, not to the concatenated part
+        let err = codec.decode("YQ==YW-h").unwrap_err();
+        assert_eq!(
+            DecodeError::InvalidChar {
+                char: '-',
+                char_index: 6,
+                byte_offset: 6,
+            },
+            err
+        );
+        let codec = "kanji".parse::<&Encoding>().unwrap();
+        let err = codec.decode("𠕊々𠖫a").unwrap_err();
+        assert_eq!(
+            DecodeError::InvalidChar {
+                char: 'a',
+                char_index: 3,
+                byte_offset: 11,
+            },
+            err
+        );
+        assert_eq!(Err(DecodeError::InvalidPadding), codec.decode("々"));
+        let codec = "hangul-block".parse::<&Encoding>().unwrap();
+        assert_eq!(
+            Err(DecodeError::InvalidBlock { char_index: 3 }),
+            codec.decode("륉퐢쯁흏흏흏")
+        );
+        assert_eq!(Err(DecodeError::InvalidPadding), codec.decode("걡걡"));
+        let err = " Base65".parse::<&Encoding>().err().unwrap();
+        let name = "Base65".to_owned();
+        let suggestion = Some("base64".to_owned());
+        assert_eq!(DecodeError::UnknownEncoding { name, suggestion }, err);
+    }
+
+    #[test]
+    fn test_rfc4648() {
+        // section 10
+        let data = ["", "f", "fo", "foo", "foob", "fooba", "foobar"];
+        let vectors: &[(&str, [&str; 7])] = &[
+            (
+                "base64",
+                [
+                    "", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy",
+                ],
+            ),
+            (
+                "base64url",
+                [
+                    "", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy",
+                ],
+            ),
+            (
+                "base64url-nopad",
+                ["", "Zg", "Zm8", "Zm9v", "Zm9vYg", "Zm9vYmE", "Zm9vYmFy"],
+            ),
+            (
+                "base32",
+                [
+                    "",
+                    "MY======",
+                    "MZXQ====",
+                    "MZXW6===",
+                    "MZXW6YQ=",
+                    "MZXW6YTB",
+                    "MZXW6YTBOI======",
+                ],
+            ),
+            (
+                "base32hex",
+                [
+                    "",
+                    "CO======",
+                    "CPNG====",
+                    "CPNMU===",
+                    "CPNMUOG=",
+                    "CPNMUOJ1",
+                    "CPNMUOJ1E8======",
+                ],
+            ),
+            (
+                "hex",
+                [
+                    "",
+                    "66",
+                    "666F",
+                    "666F6F",
+                    "666F6F62",
+                    "666F6F6261",
+                    "666F6F626172",
+                ],
+            ),
+            // the RFC's base16 is uppercase, i.e. `hex`
+            (
+                "base16",
+                [
+                    "",
+                    "66",
+                    "666f",
+                    "666f6f",
+                    "666f6f62",
+                    "666f6f6261",
+                    "666f6f626172",
+                ],
+            ),
+        ];
+        for (name, expected) in vectors {
+            let codec = name.parse::<&Encoding>().unwrap();
+            for (data, text) in data.iter().zip(expected.iter()) {
+                assert_eq!(
+                    *text,
+                    codec.encode(data.as_bytes()),
+                    "`{}` of {:?}",
+                    name,
+                    data
+                );
+                let dec = codec.decode_strict(text).unwrap();
+                assert_eq!(data.as_bytes(), &dec[..], "`{}` of {:?}", name, text);
+            }
+        }
+
+        // the alphabets differ past the letters and digits
+        let data = [0xfb, 0xff, 0xbf];
+        let encode = |name: &str| name.parse::<&Encoding>().unwrap().encode(&data);
+        assert_eq!("+/+/", encode("base64"));
+        assert_eq!("-_-_", encode("base64url"));
+        assert_eq!("-_-_", encode("base64url-nopad"));
+        assert_eq!("7P736===", encode("base32"));
+        assert_eq!("VFVRU===", encode("base32hex"));
+    }
+
+    #[test]
+    fn test_omit_pad() {
+        let nopad = "base64url-nopad".parse::<&Encoding>().unwrap();
+        // padding is still understood, as a separator too
+        assert_eq!(b"f".to_vec(), nopad.decode("Zg==").unwrap());
+        assert_eq!(b"ffo".to_vec(), nopad.decode("Zg==Zm8").unwrap());
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            nopad.decode_strict("Zg==")
+        );
+        assert_eq!(Err(DecodeError::InvalidPadding), nopad.decode_strict("Z"));
+        let b32 = "base32".parse::<&Encoding>().unwrap();
+        for text in &["MY", "MY=", "M=======", "MZX====="] {
+            assert_eq!(
+                Err(DecodeError::InvalidPadding),
+                b32.decode_strict(text),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            Err(DecodeError::TrailingBits),
+            b32.decode_strict("MZ======")
+        );
+        for n in 0..12 {
+            let len = nopad.encoded_len(n);
+            assert_eq!(len.chars, (n * 8).div_ceil(6));
+            assert_eq!(n, nopad.max_payload_for(len.chars));
+        }
+    }
+
+    #[test]
+    fn test_basehangul() {
+        let codec = "basehangul".parse::<&Encoding>().unwrap();
+        assert_eq!(10, codec.bitcount());
+        assert_eq!(Some(2), codec.tail_bits());
+        // the examples of the spec
+        let vectors = [
+            ("1", "꺽흐흐흐"),
+            ("12", "꺽먈흐흐"),
+            ("123", "꺽먹꺄흐"),
+            ("1234", "꺽먹께빎"),
+            ("123ab", "꺽먹꼍녜"),
+        ];
+        for (data, text) in &vectors {
+            assert_eq!(*text, codec.encode(data.as_bytes()));
+            assert_eq!(data.as_bytes(), &codec.decode_strict(text).unwrap()[..]);
+        }
+        // the low 2 bits of the 4th byte pick the tail char
+        assert_eq!("꺽먹께빗", codec.encode(b"1237"));
+
+        // tail chars end concatenated parts
+        assert_eq!(b"12341".to_vec(), codec.decode("꺽먹께빎꺽흐흐흐").unwrap());
+        assert_eq!(
+            Err(DecodeError::InvalidChar {
+                char: '빎',
+                char_index: 3,
+                byte_offset: 9,
+            }),
+            codec.decode_strict("꺽먹께빎꺽흐흐흐")
+        );
+        // but only where the leftover bits fit in them
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            codec.decode_strict("꺽빎흐흐")
+        );
+        assert_eq!(6, codec.max_decoded_len("꺽먹께빎꺽먈흐흐"));
+    }
+
+    #[test]
+    fn test_base65536() {
+        let codec = "base65536".parse::<&Encoding>().unwrap();
+        // from the README of the reference implementation
+        assert_eq!("驨ꍬ啯𒁷ꍲᕤ", codec.encode(b"hello world"));
+        let vectors: [(&[u8], &str); 5] = [
+            (b"", ""),
+            (b"a", "ᕡ"),
+            (b"\xff\xff", "𨗿"),
+            (&[0; 15], "㐀㐀㐀㐀㐀㐀㐀ᔀ"),
+            (&[0, 1, 2, 3, 4, 5, 6, 7], "㔀㜂㤄㬆"),
+        ];
+        for (data, text) in vectors.iter() {
+            assert_eq!(*text, codec.encode(data));
+            assert_eq!(*data, &codec.decode_strict(text).unwrap()[..]);
+        }
+        // a lone byte only ever ends a part
+        assert_eq!(b"aa".to_vec(), codec.decode("ᕡᕡ").unwrap());
+        assert_eq!(Err(DecodeError::InvalidPadding), codec.decode_strict("ᕡ="));
+    }
+
+    #[test]
+    fn test_base32768() {
+        let codec = "base32768".parse::<&Encoding>().unwrap();
+        assert_eq!(15, codec.bitcount());
+        assert_eq!("媒腻㐤┖ꈳ埳", codec.encode(b"hello world"));
+        // the bits past the data are set, in tail chars too
+        let vectors: [(&[u8], &str); 6] = [
+            (b"a", "坟"),
+            (b"ab", "圑ɟ"),
+            (b"abc", "圑㼿"),
+            (b"\xff", "ꡟ"),
+            (b"\xff\xff", "ꡟʟ"),
+            (&[0; 15], "ҠҠҠҠҠҠҠҠ"),
+        ];
+        for (data, text) in vectors.iter() {
+            assert_eq!(*text, codec.encode(data));
+            assert_eq!(*data, &codec.decode_strict(text).unwrap()[..]);
+        }
+        // clear padding bits are understood, but aren't canonical
+        assert_eq!(b"ab".to_vec(), codec.decode("圑ɀ").unwrap());
+        assert_eq!(Err(DecodeError::TrailingBits), codec.decode_strict("圑ɀ"));
+        assert_eq!(Err(DecodeError::TrailingBits), codec.decode_strict("坠"));
+        assert_eq!(b"abab".to_vec(), codec.decode("圑ɟ圑ɟ").unwrap());
+    }
+
+    #[test]
+    fn test_base2048() {
+        let codec = "base2048".parse::<&Encoding>().unwrap();
+        assert_eq!(11, codec.bitcount());
+        assert_eq!(Some(3), codec.tail_bits());
+        assert_eq!(Some(7), codec.tail_value('7'));
+        assert_eq!(None, codec.tail_value('8'));
+        assert_eq!(None, "base64".parse::<&Encoding>().unwrap().tail_value('A'));
+        // from the README of the reference implementation
+        assert_eq!("GƸOʜeҩ", codec.encode(&[1, 2, 4, 8, 16, 32, 64, 128]));
+        // up to 3 bits left over end with a digit tail char, the rest with a regular char. The bits
+        // past the data are set in both
+        let vectors: [(&[u8], &str); 8] = [
+            (b"", ""),
+            (b"a", "ځ"),
+            (b"ab", "ٽə"),
+            (b"abc", "ٽȤ7"),
+            (b"abcd", "ٽȤൺ"),
+            (b"\xff", "ၕ"),
+            (b"hello world", "ڵϠɲණæஊಢࢷ"),
+            (&[0; 11], "88888888"),
+        ];
+        for (data, text) in vectors.iter() {
+            assert_eq!(*text, codec.encode(data));
+            assert_eq!(*data, &codec.decode_strict(text).unwrap()[..]);
+        }
+        assert_eq!(b"abc".to_vec(), codec.decode("ٽȤ6").unwrap());
+        assert_eq!(Err(DecodeError::TrailingBits), codec.decode_strict("ٽȤ6"));
+        assert_eq!(b"abcabc".to_vec(), codec.decode("ٽȤ7ٽȤ7").unwrap());
+    }
+
+    #[test]
+    fn test_emoji() {
+        let codec = "emoji".parse::<&Encoding>().unwrap();
+        assert_eq!(1024, codec.char_space.num_chars());
+        assert_eq!(10, codec.bitcount());
+        // the order is frozen
+        assert_eq!('⌚', codec.lookup.idx_to_char(0));
+        assert_eq!('\u{1F531}', codec.lookup.idx_to_char(512));
+        assert_eq!('🦯', codec.lookup.idx_to_char(1023));
+        assert_eq!("⌚🦯⌚⌚", codec.encode(&[0x00, 0x3F, 0xF0, 0x00, 0x00]));
+        assert_eq!("💽🙎🤔🌷", codec.encode(b"hello"));
+        // 5 bytes in 4 emoji, the rest is dropped
+        assert_eq!("💽🙎🤔🌷💽🙈🔚", codec.encode(b"hellohe"));
+        assert_eq!(
+            b"hellohe".to_vec(),
+            codec.decode_strict("💽🙎🤔🌷💽🙈🔚").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_braille() {
+        let codec = "braille".parse::<&Encoding>().unwrap();
+        assert_eq!(8, codec.bitcount());
+        let data = (0..=255).collect::<Vec<u8>>();
+        let text = codec.encode(&data);
+        // every byte is the char at its offset, and nothing else
+        let expected = (0x2800..=0x28FF).map(|x| char::from_u32(x).unwrap());
+        assert_eq!(expected.collect::<String>(), text);
+        for n in 0..=data.len() {
+            let len = codec.encoded_len(n);
+            assert_eq!(n, len.chars);
+            assert_eq!(3 * n, len.utf8);
+            assert_eq!(n, codec.encode(&data[..n]).chars().count());
+            assert_eq!(n, codec.max_payload_for(n));
+        }
+        assert_eq!("⡨⡩", codec.encode(b"hi"));
+        assert_eq!(data, codec.decode_strict(&text).unwrap());
+        assert_eq!(Err(DecodeError::InvalidPadding), codec.decode_strict("⡨⡩?"));
+    }
+
+    #[test]
+    fn test_words() {
+        use crate::repack::BitOrder::*;
+        use crate::repack::{pack_words, unpack_words, word_bits, RepackIterator};
+        assert_eq!(Some(48), word_bits(6));
+        assert_eq!(Some(64), word_bits(4));
+        assert_eq!(Some(40), word_bits(10));
+        assert_eq!(None, word_bits(13));
+
+        let bytes = (0..=255).chain((0..=255).rev()).collect::<Vec<u8>>();
+        for (width, order) in (1..=16).flat_map(|x| [(x, MsbFirst), (x, LsbFirst)]) {
+            let reference = |bytes: &[u8]| {
+                let it = bytes.iter().map(|&x| x as uVar);
+                RepackIterator::new(it, 8, width, order).collect::<Vec<uVar>>()
+            };
+            let msg = format!("width {} {:?}", width, order);
+            for n in (0..40).chain(Some(bytes.len())) {
+                let mut syms = vec![];
+                let done = pack_words(&bytes[..n], width, order, |x| syms.push(x));
+                assert_eq!(reference(&bytes[..done]), syms, "{}", msg);
+                syms.extend(reference(&bytes[done..n]));
+                assert_eq!(reference(&bytes[..n]), syms, "{}", msg);
+
+                // set high bits are ignored, like in `RepackIterator`
+                let noisy = syms.iter().map(|&x| (x | 0xFFFF << width) as u64);
+                let noisy = noisy.collect::<Vec<u64>>();
+                let mut out = vec![0; n];
+                let (used, written) = unpack_words(&noisy, width, order, &mut out);
+                let rest = RepackIterator::new(syms[used..].iter().copied(), width, 8, order);
+                for (i, x) in rest.enumerate() {
+                    if let Some(b) = out.get_mut(written + i) {
+                        *b = x as u8;
+                    }
+                }
+                assert_eq!(&bytes[..n], &out[..], "{}", msg);
+            }
+        }
+    }
+
+    /// Repack-mode encodings for which the word-at-a-time paths differ: ASCII intervals (SIMD) or
+    /// not, non-ASCII, and alphabets with more chars than they use. In both bit orders.
+    fn word_encodings() -> Vec<Encoding> {
+        use super::builder::EncodingBuilder;
+        use super::PadType::*;
+        let build = |alphabet: &str, pad| EncodingBuilder::new("test").alphabet(alphabet).pad(pad);
+        let lsb = |builder: EncodingBuilder| builder.bit_order(BitOrder::LsbFirst).build().unwrap();
+        vec![
+            lsb(build("0123456789abcdef", BlockPad('='))),
+            lsb(build("ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", BlockPad('='))),
+            lsb(EncodingBuilder::new("test")
+                .intervals(&[(0x2800, 0x28FF)])
+                .pad(DropPad('='))),
+            lsb(EncodingBuilder::new("test")
+                .intervals(&[(0x4E00, 0x9FFF), (0x3400, 0x4DBF), (0x20000, 0x2A6DF)])
+                .pad(DropPad('々'))),
+            build("ACGT", BlockPad('=')).build().unwrap(),
+            build("0123456789abcdef", BlockPad('=')).build().unwrap(),
+            build("ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", BlockPad('='))
+                .build()
+                .unwrap(),
+            EncodingBuilder::new("test")
+                .intervals(&[(0x21, 0x7E)])
+                .pad(DropPad(' '))
+                .build()
+                .unwrap(),
+            EncodingBuilder::new("test")
+                .intervals(&[(0x2800, 0x28FF)])
+                .pad(DropPad('='))
+                .build()
+                .unwrap(),
+            build(
+                "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆ",
+                DropPad('ゐ'),
+            )
+            .build()
+            .unwrap(),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_words(data in vec(any::<u8>(), 0..200), idxs in vec(any::<u32>(), 0..200), at in any::<usize>()) {
+            let custom = word_encodings();
+            let all = get_encodings().iter().map(|x| &**x).chain(custom.iter()).filter(|c| c.mode == Mode::Repack);
+            for c in all {
+                // the same as symbol by symbol
+                let it = data.iter().map(|&x| x as uVar);
+                let nchars = c.body_chars(data.len());
+                let body = RepackIterator::new(it, 8, c.bitcount(), c.bit_order)
+                    .enumerate()
+                    .map(|(i, x)| if i + 1 == nchars { c.last_symbol(x, data.len()) } else { x })
+                    .map(|x| c.lookup.idx_to_char(x));
+                let enc = c.encode(&data);
+                prop_assert_eq!(body.collect::<String>(), enc.trim_end_matches(*c.pad_char), "Failed with `{}`", c.name);
+                prop_assert_eq!(&data, &c.decode(&enc).unwrap(), "Failed with `{}`", c.name);
+
+                // tail chars end parts, leave them out
+                let base = match c.tail_bits {
+                    Some(_) => 1 << c.bitcount(),
+                    None => c.char_space.num_chars() as u32,
+                };
+                let text = idxs.iter().map(|&i| c.lookup.idx_to_char(i % base)).collect::<String>();
+                let it = idxs.iter().map(|&i| i % base);
+                let reference = RepackIterator::new(it, c.bitcount(), 8, c.bit_order).map(|x| x as u8).collect::<Vec<u8>>();
+                if let Ok(dec) = c.decode(&text) {
+                    prop_assert_eq!(&reference[..dec.len()], &dec[..], "Failed with `{}`", c.name);
+                }
+
+                // errors point at the right char, wherever it falls in the words
+                let i = at % (idxs.len() + 1);
+                let mut bad = text.chars().collect::<Vec<char>>();
+                bad.insert(i, '€');
+                let bad = bad.into_iter().collect::<String>();
+                let err = DecodeError::InvalidChar {
+                    char: '€',
+                    char_index: i,
+                    byte_offset: text.chars().take(i).map(char::len_utf8).sum(),
+                };
+                prop_assert_eq!(Err(err), c.decode(&bad), "Failed with `{}`", c.name);
+            }
+        }
+    }
 }
@@ -0,0 +1,550 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{get_encodings, header, CharSpace, Encoding, Mode, PadType};
+use crate::error::BuildError;
+use crate::repack::BitOrder;
+
+/// Builder for user-defined encodings. `build()` checks that the alphabet and padding make sense.
+///
+/// ```
+/// use basehanja::{EncodingBuilder, PadType};
+///
+/// let enc = EncodingBuilder::new("abcd")
+///     .alphabet("abcd")
+///     .pad(PadType::BlockPad('='))
+///     .build()
+///     .unwrap();
+/// assert_eq!("bcbc", enc.encode(b"f"));
+/// ```
+pub struct EncodingBuilder {
+    name: String,
+    long_name: Option<String>,
+    id: Option<u16>,
+    alphabet: Option<Alphabet>,
+    pad_char: Option<PadType>,
+    mode: Mode,
+    bit_order: BitOrder,
+    tail_bits: Option<u8>,
+}
+
+enum Alphabet {
+    Chars(Vec<char>),
+    /// inclusive code point intervals
+    Intervals(Vec<(u32, u32)>),
+}
+
+impl EncodingBuilder {
+    pub fn new(name: &str) -> Self {
+        EncodingBuilder {
+            name: name.to_owned(),
+            long_name: None,
+            id: None,
+            alphabet: None,
+            pad_char: None,
+            mode: Mode::Repack,
+            bit_order: BitOrder::MsbFirst,
+            tail_bits: None,
+        }
+    }
+
+    /// Human-readable name. Defaults to `name`
+    pub fn long_name(mut self, long_name: &str) -> Self {
+        self.long_name = Some(long_name.to_owned());
+        self
+    }
+
+    /// Stable identifier, written in headers (see `Encoding::encode_with_header()`). Must be
+    /// below `MAX_ENCODING_ID`. Ids below `MIN_CUSTOM_ID` are reserved for the built-in
+    /// encodings: they are only accepted for the same alphabet, padding and mode as the built-in
+    /// one (e.g. when loading its `Encoding::definition()`), so that headers keep their meaning.
+    pub fn id(mut self, id: u16) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Use the chars of `s`, in order, as the alphabet
+    pub fn alphabet(self, s: &str) -> Self {
+        self.chars(s.chars())
+    }
+
+    /// Use the given chars, in order, as the alphabet
+    pub fn chars<I: IntoIterator<Item = char>>(mut self, chars: I) -> Self {
+        self.alphabet = Some(Alphabet::Chars(chars.into_iter().collect()));
+        self
+    }
+
+    /// Use the given inclusive code point intervals, in order, as the alphabet
+    pub fn intervals(mut self, intervals: &[(u32, u32)]) -> Self {
+        self.alphabet = Some(Alphabet::Intervals(intervals.to_vec()));
+        self
+    }
+
+    pub fn pad(mut self, pad_char: PadType) -> Self {
+        self.pad_char = Some(pad_char);
+        self
+    }
+
+    /// Defaults to `Mode::Repack`
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Defaults to `BitOrder::MsbFirst`. Only repack mode packs bits.
+    pub fn bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Use the `2^bits` chars that follow the first `2^bitcount()` of the alphabet as tail chars:
+    /// when the bits left over by the other chars fit in `bits`, the text ends with one of them
+    /// instead of a regular char (like in BaseHangul). They also end concatenated parts, like
+    /// padding. Only repack mode has them.
+    pub fn tail_bits(mut self, bits: u8) -> Self {
+        self.tail_bits = Some(bits);
+        self
+    }
+
+    pub fn build(self) -> Result<Encoding, BuildError> {
+        let char_space = match self.alphabet.ok_or(BuildError::MissingAlphabet)? {
+            Alphabet::Chars(chars) => {
+                let mut sorted = chars.clone();
+                sorted.sort_unstable();
+                if let Some(w) = sorted.windows(2).find(|w| w[0] == w[1]) {
+                    // report the first repeated char of the alphabet, not the smallest one
+                    let c = chars
+                        .iter()
+                        .enumerate()
+                        .find(|(i, c)| chars[..*i].contains(c));
+                    return Err(BuildError::DuplicateChar(c.map_or(w[0], |x| *x.1)));
+                }
+                CharSpace::Concrete(chars.into_iter().collect())
+            }
+            Alphabet::Intervals(intervals) => CharSpace::Intervals(check_intervals(&intervals)?),
+        };
+        let pad_char = self.pad_char.ok_or(BuildError::MissingPad)?;
+        if let Some(id) = self.id.filter(|&x| x >= header::MAX_ENCODING_ID) {
+            return Err(BuildError::InvalidId(id));
+        }
+
+        let len = char_space.num_chars();
+        if len < 2 {
+            return Err(BuildError::AlphabetTooSmall(len));
+        }
+
+        let long_name = self.long_name.as_ref().unwrap_or(&self.name);
+        let mut enc = Encoding::new(&self.name, long_name, char_space, pad_char, self.mode);
+        enc.id = self.id;
+        enc.bit_order = self.bit_order;
+        if let Some(bits) = self.tail_bits {
+            let width = enc.bitcount();
+            let fits = len >= (1 << width) + (1usize << bits.min(width));
+            let bad = self.mode != Mode::Repack || bits == 0 || bits >= width;
+            if bad || !fits || !tail_ends_data(width, bits) {
+                return Err(BuildError::InvalidTailBits(bits));
+            }
+            enc = enc.with_tail_bits(bits);
+        }
+        if enc.lookup.char_to_idx(*pad_char).is_some() {
+            return Err(BuildError::PadInAlphabet(*pad_char));
+        }
+        let padded = !matches!(pad_char, PadType::DropPad(_));
+        if self.mode == Mode::Repack && padded && !lengths_distinct(enc.bitcount(), enc.tail_bits) {
+            return Err(BuildError::AmbiguousLength(enc.bitcount()));
+        }
+        if let Some(id) = enc.id.filter(|&x| x < header::MIN_CUSTOM_ID) {
+            if !same_as_builtin(&enc, id) {
+                return Err(BuildError::ReservedId(id));
+            }
+        }
+        Ok(enc)
+    }
+}
+
+/// Does `enc` encode like the built-in encoding with the reserved `id`? Names don't matter.
+fn same_as_builtin(enc: &Encoding, id: u16) -> bool {
+    let builtin = match get_encodings().iter().find(|x| x.id == Some(id)) {
+        Some(x) => x,
+        None => return false,
+    };
+    let (a, b) = (enc.definition(), builtin.definition());
+    (a.mode, a.bit_order, a.tail_bits, a.pad, a.char_space)
+        == (b.mode, b.bit_order, b.tail_bits, b.pad, b.char_space)
+}
+
+/// Does every tail char of `tail_bits` bits hold fewer than 8 bits past the data? Otherwise those
+/// could hold one more byte.
+fn tail_ends_data(width: u8, tail_bits: u8) -> bool {
+    let width = width as usize;
+    let tail_bits = tail_bits as usize;
+    (1..=width)
+        .map(|n| n * 8 % width)
+        .filter(|&left| left != 0 && left <= tail_bits)
+        .all(|left| tail_bits - left < 8)
+}
+
+/// Do the repack-mode texts of `n` and `n + 1` bytes differ, for every `n`, without `DropPad`
+/// padding? With chars wider than a byte both can take as many chars, and only a tail char at the
+/// end of one of them tells them apart.
+fn lengths_distinct(width: u8, tail_bits: Option<u8>) -> bool {
+    let width = width as usize;
+    let tail_bits = tail_bits.unwrap_or(0) as usize;
+    // `left` is how many bits of the last char hold data
+    let ends_with_tail = |left: usize| !left.is_multiple_of(width) && left <= tail_bits;
+    (1..=width)
+        .map(|n| n * 8 % width)
+        .filter(|&left| left != 0 && left + 8 <= width)
+        .all(|left| ends_with_tail(left) != ends_with_tail(left + 8))
+}
+
+/// Convert code point intervals to char intervals, rejecting surrogates, non-chars and overlaps
+fn check_intervals(intervals: &[(u32, u32)]) -> Result<Vec<(char, char)>, BuildError> {
+    const SURROGATES: (u32, u32) = (0xD800, 0xDFFF);
+
+    let mut acc = Vec::with_capacity(intervals.len());
+    for &(a, b) in intervals {
+        if a > b {
+            return Err(BuildError::InvalidInterval(a, b));
+        }
+        if a <= SURROGATES.1 && b >= SURROGATES.0 {
+            return Err(BuildError::InvalidScalar(core::cmp::max(a, SURROGATES.0)));
+        }
+        let to_char = |u| char::from_u32(u).ok_or(BuildError::InvalidScalar(u));
+        acc.push((to_char(a)?, to_char(b)?));
+    }
+
+    let mut sorted = acc.clone();
+    sorted.sort();
+    for w in sorted.windows(2) {
+        if w[1].0 <= w[0].1 {
+            return Err(BuildError::DuplicateChar(w[1].0));
+        }
+    }
+    Ok(acc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lengths_distinct, tail_ends_data, EncodingBuilder};
+    use crate::encoding::definition::EncodingDef;
+    use crate::encoding::{get_encodings, Encoding, Mode, PadType};
+    use crate::error::{BuildError, DecodeError};
+    use crate::repack::BitOrder;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_same_as_builtin() {
+        let builtin = "base64".parse::<&Encoding>().unwrap();
+        let custom = EncodingBuilder::new("my64")
+            .intervals(&[
+                (0x41, 0x5A),
+                (0x61, 0x7A),
+                (0x30, 0x39),
+                (0x2B, 0x2B),
+                (0x2F, 0x2F),
+            ])
+            .pad(PadType::BlockPad('='))
+            .build()
+            .unwrap();
+        assert_eq!("my64", custom.long_name);
+        for i in 0..20 {
+            let data = "a".repeat(i);
+            let enc = custom.encode(data.as_bytes());
+            assert_eq!(builtin.encode(data.as_bytes()), enc);
+            assert_eq!(data.as_bytes(), &custom.decode(&enc).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn test_modes() {
+        for mode in &[Mode::Repack, Mode::Radix, Mode::BlockRadix] {
+            let enc = EncodingBuilder::new("digits")
+                .chars("0123456789".chars())
+                .pad(PadType::DropPad('.'))
+                .mode(*mode)
+                .build()
+                .unwrap();
+            let data = b"\x00\x01hello";
+            assert_eq!(&data[..], &enc.decode(&enc.encode(data)).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn test_bit_order() {
+        let lsb = |alphabet: &str| {
+            EncodingBuilder::new("lsb")
+                .alphabet(alphabet)
+                .pad(PadType::BlockPad('='))
+                .bit_order(BitOrder::LsbFirst)
+                .build()
+                .unwrap()
+        };
+        // the low nibble comes first
+        let hex = lsb("0123456789ABCDEF");
+        assert_eq!(BitOrder::LsbFirst, hex.bit_order());
+        assert_eq!("ACEF", hex.encode(&[0xca, 0xfe]));
+        assert_eq!(vec![0xca, 0xfe], hex.decode("ACEF").unwrap());
+
+        // 0x61 = 01|100001
+        let b64 = lsb("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/");
+        assert_eq!("hB==", b64.encode(b"a"));
+        assert_eq!(b"a".to_vec(), b64.decode_strict("hB==").unwrap());
+        // the unused bits are the high ones
+        assert_eq!(Err(DecodeError::TrailingBits), b64.decode_strict("hE=="));
+        assert_eq!(b"a".to_vec(), b64.decode("hF==").unwrap());
+
+        let data = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(data, b64.decode_strict(&b64.encode(&data)).unwrap());
+        let def = toml::to_string(&b64.definition()).unwrap();
+        assert!(def.contains("bit_order = \"lsb_first\""), "{}", def);
+        let back = Encoding::try_from(toml::from_str::<EncodingDef>(&def).unwrap()).unwrap();
+        assert_eq!(b64.encode(&data), back.encode(&data));
+        assert_ne!(
+            b64.encode(&data),
+            "base64".parse::<&Encoding>().unwrap().encode(&data)
+        );
+    }
+
+    #[test]
+    fn test_tail_bits() {
+        // octal, plus 2 tail chars of 1 bit
+        let enc = EncodingBuilder::new("oct")
+            .alphabet("01234567+-")
+            .pad(PadType::OmitPad('='))
+            .bit_order(BitOrder::LsbFirst)
+            .tail_bits(1)
+            .build()
+            .unwrap();
+        assert_eq!(Some(1), enc.tail_bits());
+        // a byte leaves 2 bits over, too many for a tail char
+        assert_eq!("002", enc.encode(&[0x80]));
+        // 2 bytes leave 1: 0x3F 0x00 = 0|000|000|0 + 00|111|111
+        assert_eq!("77000+", enc.encode(&[0x3F, 0x00]));
+        assert_eq!("00000-", enc.encode(&[0x00, 0x80]));
+        assert_eq!(vec![0, 0x80, 0x3F, 0], enc.decode("00000-77000+").unwrap());
+
+        let data = (0..=255).collect::<Vec<u8>>();
+        let def = toml::to_string(&enc.definition()).unwrap();
+        assert!(def.contains("tail_bits = 1"), "{}", def);
+        let back = Encoding::try_from(toml::from_str::<EncodingDef>(&def).unwrap()).unwrap();
+        for n in 0..data.len() {
+            let text = back.encode(&data[..n]);
+            assert_eq!(enc.encode(&data[..n]), text);
+            assert_eq!(&data[..n], &back.decode_strict(&text).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn test_ones_pad_tail_bits() {
+        // An arbitrary layout of 11-bit chars with 3-bit tail chars, not any published encoding
+        let enc = EncodingBuilder::new("tail")
+            .intervals(&[(0x4E00, 0x55FF), (0x30, 0x37)])
+            .pad(PadType::OnesPad('='))
+            .tail_bits(3)
+            .build()
+            .unwrap();
+        let vectors = [
+            ("a", "儏"),
+            ("ab", "儋亿"),
+            ("abc", "儋亘7"),
+            ("abcd", "儋亘哉"),
+            ("hello world", "元佛付哲主厽呍剤"),
+        ];
+        for (data, text) in &vectors {
+            assert_eq!(*text, enc.encode(data.as_bytes()));
+            assert_eq!(data.as_bytes(), &enc.decode_strict(text).unwrap()[..]);
+        }
+        assert_eq!(Err(DecodeError::TrailingBits), enc.decode_strict("儋亘6"));
+        assert_eq!(b"abc".to_vec(), enc.decode("儋亘6").unwrap());
+    }
+
+    #[test]
+    fn test_wide_chars_lengths() {
+        // chars of 9 to 16 bits can leave a whole byte unused in the last one
+        for width in 9..=16u8 {
+            let len = (1u32 << width) + (1 << (width - 1));
+            let block = (1..).map(|k| k * width as usize).find(|x| x % 8 == 0);
+            let max_len = 2 * block.unwrap() / 8;
+            let pads = [
+                PadType::BlockPad('='),
+                PadType::OmitPad('='),
+                PadType::OnesPad('='),
+                PadType::DropPad('='),
+            ];
+            for pad in &pads {
+                for tail in (0..width).map(|x| Some(x).filter(|&x| x > 0)) {
+                    let mut builder = EncodingBuilder::new("wide")
+                        .intervals(&[(0x10000, 0x10000 + len - 1)])
+                        .pad(*pad);
+                    if let Some(bits) = tail {
+                        builder = builder.tail_bits(bits);
+                    }
+                    let enc = match builder.build() {
+                        Ok(enc) => enc,
+                        // tail chars of a byte or more can also hold one byte too many
+                        Err(BuildError::InvalidTailBits(bits)) if bits >= 8 => continue,
+                        Err(e) => {
+                            assert_eq!(BuildError::AmbiguousLength(width), e);
+                            continue;
+                        }
+                    };
+                    assert!(tail.is_some() || matches!(pad, PadType::DropPad(_)));
+                    for n in 0..=max_len {
+                        let data = (0..n).map(|i| i as u8 ^ 0xA5).collect::<Vec<_>>();
+                        let text = enc.encode(&data);
+                        let ctx = (width, pad, tail, n);
+                        assert_eq!(data, enc.decode(&text).unwrap(), "{:?}", ctx);
+                        assert_eq!(data, enc.decode_strict(&text).unwrap(), "{:?}", ctx);
+                    }
+                }
+            }
+        }
+        // bytes past the data used to come back as zeros
+        let enc = EncodingBuilder::new("12")
+            .intervals(&[(0x4E00, 0x5DFF)])
+            .pad(PadType::BlockPad('='));
+        assert_eq!(Some(BuildError::AmbiguousLength(12)), enc.build().err());
+    }
+
+    #[test]
+    fn test_builtins_lengths() {
+        // the built-ins skip the builder, but must pass its checks too
+        for enc in get_encodings().iter().filter(|x| x.mode == Mode::Repack) {
+            let width = enc.bitcount();
+            if let Some(bits) = enc.tail_bits {
+                assert!(tail_ends_data(width, bits), "{}", enc.name);
+            }
+            if !matches!(enc.pad_char, PadType::DropPad(_)) {
+                assert!(lengths_distinct(width, enc.tail_bits), "{}", enc.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserved_ids() {
+        let base64 = "base64".parse::<&Encoding>().unwrap();
+        // a built-in's own definition, whatever its name
+        let mut def = base64.definition();
+        def.name = "my64".to_owned();
+        def.long_name = None;
+        assert_eq!(Some(3), Encoding::try_from(def.clone()).unwrap().id());
+        // but nothing else that would read as it in headers
+        def.pad = PadType::OmitPad('=');
+        assert_eq!(
+            Some(BuildError::ReservedId(3)),
+            Encoding::try_from(def).err()
+        );
+        let hex = EncodingBuilder::new("hex")
+            .intervals(&[(0x30, 0x39), (0x41, 0x46)])
+            .pad(PadType::BlockPad('?'));
+        assert_eq!(Some(2), hex.id(2).build().unwrap().id());
+        // ids no built-in has yet are reserved too
+        let unused = EncodingBuilder::new("x")
+            .alphabet("01")
+            .pad(PadType::DropPad('='));
+        assert_eq!(
+            Some(BuildError::ReservedId(200)),
+            unused.id(200).build().err()
+        );
+        let custom = EncodingBuilder::new("x")
+            .alphabet("01")
+            .pad(PadType::DropPad('='));
+        assert_eq!(Some(256), custom.id(256).build().unwrap().id());
+    }
+
+    #[test]
+    fn test_rejects() {
+        let pad = PadType::DropPad('=');
+        let cases = vec![
+            (
+                EncodingBuilder::new("x").pad(pad),
+                BuildError::MissingAlphabet,
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("01"),
+                BuildError::MissingPad,
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("0").pad(pad),
+                BuildError::AlphabetTooSmall(1),
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("0120").pad(pad),
+                BuildError::DuplicateChar('0'),
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("01=").pad(pad),
+                BuildError::PadInAlphabet('='),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .intervals(&[(0x30, 0x3F)])
+                    .pad(pad),
+                BuildError::PadInAlphabet('='),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .intervals(&[(0x30, 0x39), (0x35, 0x40)])
+                    .pad(pad),
+                BuildError::DuplicateChar('5'),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .intervals(&[(0xD000, 0xE000)])
+                    .pad(pad),
+                BuildError::InvalidScalar(0xD800),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .intervals(&[(0x10FFFF, 0x110000)])
+                    .pad(pad),
+                BuildError::InvalidScalar(0x110000),
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("01").pad(pad).id(4096),
+                BuildError::InvalidId(4096),
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("01").pad(pad).id(3),
+                BuildError::ReservedId(3),
+            ),
+            (
+                EncodingBuilder::new("x").alphabet("01").pad(pad).id(255),
+                BuildError::ReservedId(255),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .intervals(&[(0x39, 0x30)])
+                    .pad(pad),
+                BuildError::InvalidInterval(0x39, 0x30),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .alphabet("0123")
+                    .pad(pad)
+                    .tail_bits(1),
+                BuildError::InvalidTailBits(1),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .alphabet("012345")
+                    .pad(pad)
+                    .tail_bits(2),
+                BuildError::InvalidTailBits(2),
+            ),
+            (
+                EncodingBuilder::new("x")
+                    .alphabet("012345")
+                    .pad(pad)
+                    .mode(Mode::BlockRadix)
+                    .tail_bits(1),
+                BuildError::InvalidTailBits(1),
+            ),
+        ];
+        for (builder, err) in cases {
+            assert_eq!(Some(err), builder.build().err());
+        }
+    }
+}
@@ -1,67 +1,590 @@
-use super::{CharSpace, Encoding, PadType};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use once_cell::race::OnceBox;
 
+use super::{CharSpace, Encoding, Mode, PadType};
+use crate::repack::BitOrder;
+
+use Mode::*;
 use PadType::*;
 
-pub static ENCODINGS: &[Encoding] = &[
-    Encoding {
-        name: "binary",
-        long_name: "Binary",
-        char_space: CharSpace::Concrete("01"),
-        pad_char: BlockPad('?'), // it's not going to be used...
-    },
-    Encoding {
-        name: "hex",
-        long_name: "Hexadecimal",
-        char_space: CharSpace::Intervals(&[('0', '9'), ('A', 'F')]),
-        pad_char: BlockPad('?'), // it's not going to be used...
-    },
-    Encoding {
-        name: "base64",
-        long_name: "Base64",
-        char_space: CharSpace::Intervals(&[
-            ('A', 'Z'),
-            ('a', 'z'),
-            ('0', '9'),
-            ('+', '+'),
-            ('/', '/'),
-        ]),
-        pad_char: BlockPad('='),
-    },
-    Encoding {
-        name: "hiragana",
-        long_name: "Hiragana (ひらがな)",
-        char_space: CharSpace::Concrete(
-            // ordering mostly follows https://www.youtube.com/watch?v=lrMkJAzbWQc
-            "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみ\
-            むめもやゆよらりるれろわをんがぎぐげござじずぜぞだぢづでどばびぶ",
-        ),
-        pad_char: BlockPad('ゐ'),
-    },
-    Encoding {
-        name: "katakana",
-        long_name: "Katakana (かたかな)",
-        char_space: CharSpace::Concrete(
-            "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミ\
-            ムメモヤユヨラリルレロワヲンガギグゲゴザジズゼゾダヂヅデドバビブ",
-        ),
-        pad_char: BlockPad('ヰ'),
-    },
-    Encoding {
-        name: "hangul",
-        long_name: "Hangul (한글) (13-bit)",
-        char_space: CharSpace::Intervals(&[
-            ('\u{AC00}', '\u{D74f}'), // 11_088 chars
-        ]),
-        pad_char: DropPad('흐'),
-    },
-    Encoding {
-        name: "kanji",
-        long_name: "Hanzi+Kanji+Hanja (漢字)",
-        char_space: CharSpace::Intervals(&[
-            ('\u{04e00}', '\u{09fff}'), // 20_992 chars; https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_(Unicode_block)
-            ('\u{03400}', '\u{03DB5}'), //  6_592 chars; https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_Extension_A
-            ('\u{20000}', '\u{2a6df}'), // 42_720 chars; https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_Extension_B
-        ]),
-        pad_char: DropPad('々'),
-    },
-];
+static ENCODINGS: OnceBox<Vec<Arc<Encoding>>> = OnceBox::new();
+
+/// Built-in encodings. Their ids are stable, never reuse or change them
+pub fn encodings() -> &'static Vec<Arc<Encoding>> {
+    ENCODINGS.get_or_init(|| {
+        let encodings = vec![
+            Encoding::new(
+                "binary",
+                "Binary",
+                CharSpace::Concrete("01".to_owned()),
+                BlockPad('?'), // it's not going to be used...
+                Repack,
+            )
+            .with_id(1),
+            Encoding::new(
+                "hex",
+                "Hexadecimal",
+                CharSpace::Intervals(vec![('0', '9'), ('A', 'F')]),
+                BlockPad('?'), // it's not going to be used...
+                Repack,
+            )
+            .with_id(2),
+            Encoding::new(
+                "base64",
+                "Base64",
+                CharSpace::Intervals(vec![
+                    ('A', 'Z'),
+                    ('a', 'z'),
+                    ('0', '9'),
+                    ('+', '+'),
+                    ('/', '/'),
+                ]),
+                BlockPad('='),
+                Repack,
+            )
+            .with_id(3),
+            Encoding::new(
+                "hiragana",
+                "Hiragana (ひらがな)",
+                CharSpace::Concrete(
+                    // ordering mostly follows https://www.youtube.com/watch?v=lrMkJAzbWQc
+                    "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみ\
+                むめもやゆよらりるれろわをんがぎぐげござじずぜぞだぢづでどばびぶ"
+                        .to_owned(),
+                ),
+                BlockPad('ゐ'),
+                Repack,
+            )
+            .with_id(4),
+            Encoding::new(
+                "katakana",
+                "Katakana (かたかな)",
+                CharSpace::Concrete(
+                    "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミ\
+                ムメモヤユヨラリルレロワヲンガギグゲゴザジズゼゾダヂヅデドバビブ"
+                        .to_owned(),
+                ),
+                BlockPad('ヰ'),
+                Repack,
+            )
+            .with_id(5),
+            Encoding::new(
+                "hangul",
+                "Hangul (한글) (13-bit)",
+                CharSpace::Intervals(vec![
+                    ('\u{AC00}', '\u{D74f}'), // 11_088 chars
+                ]),
+                DropPad('흐'),
+                Repack,
+            )
+            .with_id(6),
+            Encoding::new(
+                "kanji",
+                "Hanzi+Kanji+Hanja (漢字)",
+                CharSpace::Intervals(vec![
+                    ('\u{04e00}', '\u{09fff}'), // 20_992 chars; https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_(Unicode_block)
+                    ('\u{03400}', '\u{03DB5}'), //  6_592 chars; https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_Extension_A
+                    ('\u{20000}', '\u{2a6df}'), // 42_720 chars; https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_Extension_B
+                ]),
+                DropPad('々'),
+                Repack,
+            )
+            .with_id(7),
+            Encoding::new(
+                "kanji-radix",
+                "Hanzi+Kanji+Hanja (漢字) (radix)",
+                CharSpace::Intervals(vec![
+                    ('\u{04e00}', '\u{09fff}'), // 20_992 chars
+                    ('\u{03400}', '\u{04dbf}'), //  6_592 chars
+                    ('\u{20000}', '\u{2a6df}'), // 42_720 chars
+                ]),
+                DropPad('々'),
+                Radix,
+            )
+            .with_id(8),
+            Encoding::new(
+                "hangul-block",
+                "Hangul (한글) (5 bytes in 3 chars)",
+                CharSpace::Intervals(vec![
+                    ('\u{AC00}', '\u{D74f}'), // 11_088 chars
+                ]),
+                DropPad('흐'),
+                BlockRadix,
+            )
+            .with_id(9),
+            // RFC 4648 (https://www.rfc-editor.org/rfc/rfc4648)
+            Encoding::new(
+                "base32",
+                "Base32 (RFC 4648)",
+                CharSpace::Intervals(vec![('A', 'Z'), ('2', '7')]),
+                BlockPad('='),
+                Repack,
+            )
+            .with_id(10),
+            Encoding::new(
+                "base32hex",
+                "Base32 with extended hex alphabet (RFC 4648)",
+                CharSpace::Intervals(vec![('0', '9'), ('A', 'V')]),
+                BlockPad('='),
+                Repack,
+            )
+            .with_id(11),
+            Encoding::new(
+                "base64url",
+                "Base64 URL and filename safe (RFC 4648)",
+                CharSpace::Intervals(vec![
+                    ('A', 'Z'),
+                    ('a', 'z'),
+                    ('0', '9'),
+                    ('-', '-'),
+                    ('_', '_'),
+                ]),
+                BlockPad('='),
+                Repack,
+            )
+            .with_id(12),
+            Encoding::new(
+                "base64url-nopad",
+                "Base64 URL and filename safe, unpadded (RFC 4648)",
+                CharSpace::Intervals(vec![
+                    ('A', 'Z'),
+                    ('a', 'z'),
+                    ('0', '9'),
+                    ('-', '-'),
+                    ('_', '_'),
+                ]),
+                OmitPad('='),
+                Repack,
+            )
+            .with_id(13),
+            Encoding::new(
+                "base16",
+                "Base16 (RFC 4648), lowercase",
+                CharSpace::Intervals(vec![('0', '9'), ('a', 'f')]),
+                BlockPad('='), // it's not going to be used...
+                Repack,
+            )
+            .with_id(14),
+            // https://github.com/basehangul/basehangul-javascript
+            Encoding::new(
+                "basehangul",
+                "BaseHangul (베이스한글)",
+                CharSpace::Concrete(
+                    // the first 1024 syllables of KS X 1001 (i.e. in EUC-KR order), then the 4
+                    // tail chars
+                    "가각간갇갈갉갊감갑값갓갔강갖갗같갚갛개객갠갤갬갭갯갰갱갸갹갼걀걋걍걔걘걜거걱건걷걸걺검겁것겄겅\
+                    겆겉겊겋게겐겔겜겝겟겠겡겨격겪견겯결겸겹겻겼경곁계곈곌곕곗고곡곤곧골곪곬곯곰곱곳공곶과곽관괄괆\
+                    괌괍괏광괘괜괠괩괬괭괴괵괸괼굄굅굇굉교굔굘굡굣구국군굳굴굵굶굻굼굽굿궁궂궈궉권궐궜궝궤궷귀귁귄\
+                    귈귐귑귓규균귤그극근귿글긁금급긋긍긔기긱긴긷길긺김깁깃깅깆깊까깍깎깐깔깖깜깝깟깠깡깥깨깩깬깰깸\
+                    깹깻깼깽꺄꺅꺌꺼꺽꺾껀껄껌껍껏껐껑께껙껜껨껫껭껴껸껼꼇꼈꼍꼐꼬꼭꼰꼲꼴꼼꼽꼿꽁꽂꽃꽈꽉꽐꽜꽝꽤\
+                    꽥꽹꾀꾄꾈꾐꾑꾕꾜꾸꾹꾼꿀꿇꿈꿉꿋꿍꿎꿔꿜꿨꿩꿰꿱꿴꿸뀀뀁뀄뀌뀐뀔뀜뀝뀨끄끅끈끊끌끎끓끔끕끗끙\
+                    끝끼끽낀낄낌낍낏낑나낙낚난낟날낡낢남납낫났낭낮낯낱낳내낵낸낼냄냅냇냈냉냐냑냔냘냠냥너넉넋넌널넒\
+                    넓넘넙넛넜넝넣네넥넨넬넴넵넷넸넹녀녁년녈념녑녔녕녘녜녠노녹논놀놂놈놉놋농높놓놔놘놜놨뇌뇐뇔뇜뇝\
+                    뇟뇨뇩뇬뇰뇹뇻뇽누눅눈눋눌눔눕눗눙눠눴눼뉘뉜뉠뉨뉩뉴뉵뉼늄늅늉느늑는늘늙늚늠늡늣능늦늪늬늰늴니\
+                    닉닌닐닒님닙닛닝닢다닥닦단닫달닭닮닯닳담답닷닸당닺닻닿대댁댄댈댐댑댓댔댕댜더덕덖던덛덜덞덟덤덥\
+                    덧덩덫덮데덱덴델뎀뎁뎃뎄뎅뎌뎐뎔뎠뎡뎨뎬도독돈돋돌돎돐돔돕돗동돛돝돠돤돨돼됐되된될됨됩됫됴두둑\
+                    둔둘둠둡둣둥둬뒀뒈뒝뒤뒨뒬뒵뒷뒹듀듄듈듐듕드득든듣들듦듬듭듯등듸디딕딘딛딜딤딥딧딨딩딪따딱딴딸\
+                    땀땁땃땄땅땋때땍땐땔땜땝땟땠땡떠떡떤떨떪떫떰떱떳떴떵떻떼떽뗀뗄뗌뗍뗏뗐뗑뗘뗬또똑똔똘똥똬똴뙈뙤\
+                    뙨뚜뚝뚠뚤뚫뚬뚱뛔뛰뛴뛸뜀뜁뜅뜨뜩뜬뜯뜰뜸뜹뜻띄띈띌띔띕띠띤띨띰띱띳띵라락란랄람랍랏랐랑랒랖랗\
+                    래랙랜랠램랩랫랬랭랴략랸럇량러럭런럴럼럽럿렀렁렇레렉렌렐렘렙렛렝려력련렬렴렵렷렸령례롄롑롓로록\
+                    론롤롬롭롯롱롸롼뢍뢨뢰뢴뢸룀룁룃룅료룐룔룝룟룡루룩룬룰룸룹룻룽뤄뤘뤠뤼뤽륀륄륌륏륑류륙륜률륨륩\
+                    륫륭르륵른를름릅릇릉릊릍릎리릭린릴림립릿링마막만많맏말맑맒맘맙맛망맞맡맣매맥맨맬맴맵맷맸맹맺먀\
+                    먁먈먕머먹먼멀멂멈멉멋멍멎멓메멕멘멜멤멥멧멨멩며멱면멸몃몄명몇몌모목몫몬몰몲몸몹못몽뫄뫈뫘뫙뫼\
+                    묀묄묍묏묑묘묜묠묩묫무묵묶문묻물묽묾뭄뭅뭇뭉뭍뭏뭐뭔뭘뭡뭣뭬뮈뮌뮐뮤뮨뮬뮴뮷므믄믈믐믓미믹민믿\
+                    밀밂밈밉밋밌밍및밑바박밖밗반받발밝밞밟밤밥밧방밭배백밴밸뱀뱁뱃뱄뱅뱉뱌뱍뱐뱝버벅번벋벌벎범법벗\
+                    벙벚베벡벤벧벨벰벱벳벴벵벼벽변별볍볏볐병볕볘볜보복볶본볼봄봅봇봉봐봔봤봬뵀뵈뵉뵌뵐뵘뵙뵤뵨부북\
+                    분붇불붉붊붐붑붓붕붙붚붜붤붰붸뷔뷕뷘뷜뷩뷰뷴뷸븀븃븅브븍븐블븜븝븟비빅빈빌\
+                    빎빔빕빗"
+                        .to_owned(),
+                ),
+                BlockPad('흐'),
+                Repack,
+            )
+            .with_id(15)
+            .with_tail_bits(2),
+            // https://github.com/qntm/base65536, 2 bytes per char
+            Encoding::new(
+                "base65536",
+                "Base65536",
+                CharSpace::Intervals(vec![
+                    ('\u{03400}', '\u{04CFF}'),
+                    ('\u{04E00}', '\u{09EFF}'),
+                    ('\u{0A100}', '\u{0A3FF}'),
+                    ('\u{0A500}', '\u{0A5FF}'),
+                    ('\u{10600}', '\u{106FF}'),
+                    ('\u{12000}', '\u{122FF}'),
+                    ('\u{13000}', '\u{133FF}'),
+                    ('\u{14400}', '\u{145FF}'),
+                    ('\u{16800}', '\u{169FF}'),
+                    ('\u{20000}', '\u{285FF}'),
+                    ('\u{01500}', '\u{015FF}'), // tail chars
+                ]),
+                OmitPad('='),
+                Repack,
+            )
+            .with_id(16)
+            // the first byte of a pair is the char within a block, the second picks the block
+            .with_bit_order(BitOrder::LsbFirst)
+            .with_tail_bits(8),
+            // https://github.com/qntm/base32768, 15 bits per UTF-16 code unit
+            Encoding::new(
+                "base32768",
+                "Base32768",
+                CharSpace::Intervals(vec![
+                    ('\u{004A0}', '\u{004BF}'),
+                    ('\u{00500}', '\u{0051F}'),
+                    ('\u{00680}', '\u{006BF}'),
+                    ('\u{00760}', '\u{0079F}'),
+                    ('\u{007C0}', '\u{007DF}'),
+                    ('\u{01000}', '\u{0101F}'),
+                    ('\u{010A0}', '\u{010BF}'),
+                    ('\u{01100}', '\u{0115F}'),
+                    ('\u{01180}', '\u{0119F}'),
+                    ('\u{011E0}', '\u{0123F}'),
+                    ('\u{01260}', '\u{0127F}'),
+                    ('\u{012E0}', '\u{012FF}'),
+                    ('\u{01320}', '\u{0133F}'),
+                    ('\u{013A0}', '\u{013DF}'),
+                    ('\u{01420}', '\u{0165F}'),
+                    ('\u{016A0}', '\u{016DF}'),
+                    ('\u{01780}', '\u{0179F}'),
+                    ('\u{01820}', '\u{0185F}'),
+                    ('\u{018C0}', '\u{018DF}'),
+                    ('\u{01980}', '\u{0199F}'),
+                    ('\u{019E0}', '\u{019FF}'),
+                    ('\u{01A20}', '\u{01A3F}'),
+                    ('\u{01BC0}', '\u{01BDF}'),
+                    ('\u{01C00}', '\u{01C1F}'),
+                    ('\u{01D00}', '\u{01D1F}'),
+                    ('\u{021E0}', '\u{021FF}'),
+                    ('\u{022C0}', '\u{022DF}'),
+                    ('\u{02340}', '\u{023DF}'),
+                    ('\u{02400}', '\u{0241F}'),
+                    ('\u{02500}', '\u{0275F}'),
+                    ('\u{02780}', '\u{027BF}'),
+                    ('\u{02800}', '\u{0297F}'),
+                    ('\u{029A0}', '\u{029BF}'),
+                    ('\u{02A20}', '\u{02A5F}'),
+                    ('\u{02A80}', '\u{02ABF}'),
+                    ('\u{02AE0}', '\u{02B5F}'),
+                    ('\u{02C00}', '\u{02C1F}'),
+                    ('\u{02C80}', '\u{02CDF}'),
+                    ('\u{02D00}', '\u{02D1F}'),
+                    ('\u{02D40}', '\u{02D5F}'),
+                    ('\u{02EA0}', '\u{02EDF}'),
+                    ('\u{031C0}', '\u{031DF}'),
+                    ('\u{03400}', '\u{04D9F}'),
+                    ('\u{04DC0}', '\u{09FBF}'),
+                    ('\u{0A000}', '\u{0A47F}'),
+                    ('\u{0A4A0}', '\u{0A4BF}'),
+                    ('\u{0A500}', '\u{0A5FF}'),
+                    ('\u{0A640}', '\u{0A65F}'),
+                    ('\u{0A6A0}', '\u{0A6DF}'),
+                    ('\u{0A700}', '\u{0A75F}'),
+                    ('\u{0A780}', '\u{0A79F}'),
+                    ('\u{0A840}', '\u{0A85F}'),
+                    ('\u{00180}', '\u{0019F}'), // tail chars
+                    ('\u{00240}', '\u{0029F}'),
+                ]),
+                OnesPad('='),
+                Repack,
+            )
+            .with_id(17)
+            .with_tail_bits(7),
+            Encoding::new(
+                "emoji",
+                "Emoji",
+                CharSpace::Concrete(
+                    // the fully-qualified emoji made of a single code point (so neither variation
+                    // selectors nor ZWJ sequences) of emoji-test.txt 17.0, sorted by the Emoji
+                    // version that introduced them (the oldest render the most widely), then by
+                    // code point. The first 1024, up to U+1F9AF of Emoji 12.0, except for
+                    // the pad char. Never reorder them
+                    "⌚⌛⏩⏪⏫⏬⏰⏳◽◾☔☕♈♉♊♋♌♍♎♏♐♑♒♓♿⚓⚡⚪⚫⚽⚾⛄\
+                    ⛅⛎⛔⛪⛲⛳⛵⛺⛽✅✊✋✨❌❎❓❔❕❗➕➖➗➰⬛⬜⭐⭕🀄🃏🆎🆑🆒\
+                    🆓🆔🆕🆖🆗🆘🆙🆚🈁🈚🈯🈲🈳🈴🈵🈶🈸🈹🈺🉐🉑🌀🌁🌂🌃🌄🌅🌆🌇🌈🌉🌊\
+                    🌋🌌🌏🌑🌓🌔🌕🌙🌛🌟🌠🌰🌱🌴🌵🌷🌸🌹🌺🌻🌼🌽🌾🌿🍀🍁🍂🍃🍄🍅🍆🍇\
+                    🍈🍉🍊🍌🍍🍎🍏🍑🍒🍓🍔🍕🍖🍗🍘🍙🍚🍛🍜🍝🍞🍟🍠🍡🍢🍣🍤🍥🍦🍧🍨🍩\
+                    🍪🍫🍬🍭🍮🍯🍰🍱🍲🍳🍴🍵🍶🍷🍸🍹🍺🍻🎀🎁🎂🎃🎄🎅🎆🎇🎈🎉🎊🎋🎌🎍\
+                    🎎🎏🎐🎑🎒🎓🎠🎡🎢🎣🎤🎥🎦🎧🎨🎩🎪🎫🎬🎭🎮🎯🎰🎱🎲🎳🎴🎵🎶🎷🎸🎹\
+                    🎺🎻🎼🎽🎾🎿🏀🏁🏂🏃🏄🏆🏈🏊🏠🏡🏢🏣🏥🏦🏧🏨🏩🏪🏫🏬🏭🏮🏯🏰🐌🐍\
+                    🐎🐑🐒🐔🐗🐘🐙🐚🐛🐜🐝🐞🐟🐠🐡🐢🐣🐤🐥🐦🐧🐨🐩🐫🐬🐭🐮🐯🐰🐱🐲🐳\
+                    🐴🐵🐶🐷🐸🐹🐺🐻🐼🐽🐾👀👂👃👄👅👆👇👈👉👊👋👌👍👎👏👐👑👒👓👔👕\
+                    👖👗👘👙👚👛👜👝👞👟👠👡👢👣👤👦👧👨👩👪👫👮👯👰👱👲👳👴👵👶👷👸\
+                    👹👺👻👼👽👾👿💀💁💂💃💄💅💆💇💈💉💊💋💌💍💎💏💐💑💒💓💔💕💖💗💘\
+                    💙💚💛💜💝💞💟💠💡💢💣💤💥💦💧💨💩💪💫💬💮💯💰💱💲💳💴💵💸💹💺💻\
+                    💼💽💾💿📀📁📂📃📄📅📆📇📈📉📊📋📌📍📎📏📐📑📒📓📔📕📖📗📘📙📚📛\
+                    📜📝📞📟📠📡📢📣📤📥📦📧📨📩📪📫📮📰📱📲📳📴📶📷📹📺📻📼🔃🔊🔋🔌\
+                    🔍🔎🔏🔐🔑🔒🔓🔔🔖🔗🔘🔙🔛🔜🔝🔞🔟🔠🔡🔢🔣🔤🔥🔦🔧🔨🔩🔪🔫🔮🔯🔰\
+                    🔱🔲🔳🔴🔵🔶🔷🔸🔹🔺🔻🔼🔽🕐🕑🕒🕓🕔🕕🕖🕗🕘🕙🕚🕛🗻🗼🗽🗾🗿😁😂\
+                    😃😄😅😆😉😊😋😌😍😏😒😓😔😖😘😚😜😝😞😠😡😢😣😤😥😨😩😪😫😭😰😱\
+                    😲😳😵😷😸😹😺😻😼😽😾😿🙀🙅🙆🙇🙈🙉🙊🙋🙌🙍🙎🙏🚀🚃🚄🚅🚇🚉🚌🚏\
+                    🚑🚒🚓🚕🚗🚙🚚🚢🚤🚥🚧🚨🚩🚪🚫🚬🚭🚲🚶🚹🚺🚻🚼🚽🚾🛀🌍🌎🌜🐈🐕📬\
+                    📭🔈🕜🕝🕞🕟🕠🕡🕢🕣🕤🕥🕦🕧😐🚍🚔🚘➿🌐🌒🌖🌗🌘🌚🌝🌞🌭🌮🌯🌲🌳\
+                    🍋🍐🍼🍾🍿🏅🏇🏉🏏🏐🏑🏒🏓🏤🏴🏸🏹🏺🐀🐁🐂🐃🐄🐅🐆🐇🐉🐊🐋🐏🐐🐓\
+                    🐖🐪👥👬👭💭💶💷📯📵📸📿🔀🔁🔂🔄🔅🔆🔇🔉🔕🔬🔭🕋🕌🕍🕎🖕🖖😀😇😈\
+                    😎😑😕😗😙😛😟😦😧😬😮😯😴😶🙁🙂🙃🙄🚁🚂🚆🚈🚊🚋🚎🚐🚖🚛🚜🚝🚞🚟\
+                    🚠🚡🚣🚦🚮🚯🚰🚱🚳🚴🚵🚷🚸🚿🛁🛂🛃🛄🛅🛌🛐🛫🛬🤐🤑🤒🤓🤔🤕🤖🤗🤘\
+                    🦀🦁🦂🦃🦄🧀🕺🖤🛑🛒🛴🛵🛶🤙🤚🤛🤜🤝🤞🤠🤡🤢🤣🤤🤥🤦🤧🤰🤳🤴🤵🤶\
+                    🤷🤸🤹🤺🤼🤽🤾🥀🥁🥂🥃🥄🥅🥇🥈🥉🥊🥋🥐🥑🥒🥓🥔🥕🥖🥗🥘🥙🥚🥛🥜🥝\
+                    🥞🦅🦆🦇🦈🦉🦊🦋🦌🦍🦎🦏🦐🦑🛷🛸🤟🤨🤩🤪🤫🤬🤭🤮🤯🤱🤲🥌🥟🥠🥡🥢\
+                    🥣🥤🥥🥦🥧🥨🥩🥪🥫🦒🦓🦔🦕🦖🦗🧐🧑🧒🧓🧔🧕🧖🧗🧘🧙🧚🧛🧜🧝🧞🧟🧠\
+                    🧡🧢🧣🧤🧥🧦🛹🥍🥎🥏🥬🥭🥮🥯🥰🥳🥴🥵🥶🥺🥼🥽🥾🥿🦘🦙🦚🦛🦜🦝🦞🦟\
+                    🦠🦡🦢🦴🦵🦶🦷🦸🦹🧁🧂🧧🧨🧩🧪🧫🧬🧭🧮🧯🧰🧱🧲🧳🧴🧵🧶🧷🧸🧹🧺🧻\
+                    🧼🧽🧾🧿🛕🛺🟠🟡🟢🟣🟤🟥🟦🟧🟨🟩🟪🟫🤍🤎🤏🤿🥱🥻🦥🦦🦧🦨🦩🦪🦮🦯"
+                        .to_owned(),
+                ),
+                DropPad('\u{1F51A}'), // 🔚
+                Repack,
+            )
+            .with_id(18),
+            // dot n is bit n-1, so every char shows the bits of its byte
+            Encoding::new(
+                "braille",
+                "Braille patterns (⠿), 1 char per byte",
+                CharSpace::Intervals(vec![('\u{2800}', '\u{28FF}')]),
+                BlockPad('?'), // it's not going to be used...
+                Repack,
+            )
+            .with_id(19),
+            // https://github.com/qntm/base2048, 11 bits per char
+            Encoding::new(
+                "base2048",
+                "Base2048",
+                CharSpace::Intervals(vec![
+                    ('\u{00038}', '\u{00039}'),
+                    ('\u{00041}', '\u{0005A}'),
+                    ('\u{00061}', '\u{0007A}'),
+                    ('\u{000C6}', '\u{000C6}'),
+                    ('\u{000D0}', '\u{000D0}'),
+                    ('\u{000D8}', '\u{000D8}'),
+                    ('\u{000DE}', '\u{000DF}'),
+                    ('\u{000E6}', '\u{000E6}'),
+                    ('\u{000F0}', '\u{000F0}'),
+                    ('\u{000F8}', '\u{000F8}'),
+                    ('\u{000FE}', '\u{000FE}'),
+                    ('\u{00110}', '\u{00111}'),
+                    ('\u{00126}', '\u{00127}'),
+                    ('\u{00131}', '\u{00131}'),
+                    ('\u{00138}', '\u{00138}'),
+                    ('\u{00141}', '\u{00142}'),
+                    ('\u{0014A}', '\u{0014B}'),
+                    ('\u{00152}', '\u{00153}'),
+                    ('\u{00166}', '\u{00167}'),
+                    ('\u{00180}', '\u{0019F}'),
+                    ('\u{001A2}', '\u{001AE}'),
+                    ('\u{001B1}', '\u{001C3}'),
+                    ('\u{001DD}', '\u{001DD}'),
+                    ('\u{001E4}', '\u{001E5}'),
+                    ('\u{001F6}', '\u{001F7}'),
+                    ('\u{0021C}', '\u{0021D}'),
+                    ('\u{00220}', '\u{00225}'),
+                    ('\u{00234}', '\u{002AF}'),
+                    ('\u{00370}', '\u{00373}'),
+                    ('\u{00376}', '\u{00377}'),
+                    ('\u{0037B}', '\u{0037D}'),
+                    ('\u{0037F}', '\u{0037F}'),
+                    ('\u{00391}', '\u{003A1}'),
+                    ('\u{003A3}', '\u{003A9}'),
+                    ('\u{003B1}', '\u{003C9}'),
+                    ('\u{003CF}', '\u{003CF}'),
+                    ('\u{003D7}', '\u{003EF}'),
+                    ('\u{003F3}', '\u{003F3}'),
+                    ('\u{003F7}', '\u{003F8}'),
+                    ('\u{003FA}', '\u{003FF}'),
+                    ('\u{00402}', '\u{00402}'),
+                    ('\u{00404}', '\u{00406}'),
+                    ('\u{00408}', '\u{0040B}'),
+                    ('\u{0040F}', '\u{00418}'),
+                    ('\u{0041A}', '\u{00438}'),
+                    ('\u{0043A}', '\u{0044F}'),
+                    ('\u{00452}', '\u{00452}'),
+                    ('\u{00454}', '\u{00456}'),
+                    ('\u{00458}', '\u{0045B}'),
+                    ('\u{0045F}', '\u{00475}'),
+                    ('\u{00478}', '\u{00481}'),
+                    ('\u{0048A}', '\u{004C0}'),
+                    ('\u{004C3}', '\u{004CF}'),
+                    ('\u{004D4}', '\u{004D5}'),
+                    ('\u{004D8}', '\u{004D9}'),
+                    ('\u{004E0}', '\u{004E1}'),
+                    ('\u{004E8}', '\u{004E9}'),
+                    ('\u{004F6}', '\u{004F7}'),
+                    ('\u{004FA}', '\u{0052F}'),
+                    ('\u{00531}', '\u{00556}'),
+                    ('\u{00561}', '\u{00586}'),
+                    ('\u{005D0}', '\u{005EA}'),
+                    ('\u{005F0}', '\u{005F2}'),
+                    ('\u{00620}', '\u{00621}'),
+                    ('\u{00627}', '\u{0063F}'),
+                    ('\u{00641}', '\u{0064A}'),
+                    ('\u{00660}', '\u{00669}'),
+                    ('\u{0066E}', '\u{0066F}'),
+                    ('\u{00671}', '\u{00674}'),
+                    ('\u{00679}', '\u{006BF}'),
+                    ('\u{006C1}', '\u{006C1}'),
+                    ('\u{006C3}', '\u{006D2}'),
+                    ('\u{006D5}', '\u{006D5}'),
+                    ('\u{006EE}', '\u{006FC}'),
+                    ('\u{006FF}', '\u{006FF}'),
+                    ('\u{00710}', '\u{00710}'),
+                    ('\u{00712}', '\u{0072F}'),
+                    ('\u{0074D}', '\u{007A5}'),
+                    ('\u{007B1}', '\u{007B1}'),
+                    ('\u{007C0}', '\u{007EA}'),
+                    ('\u{00800}', '\u{00815}'),
+                    ('\u{00840}', '\u{00858}'),
+                    ('\u{00860}', '\u{0086A}'),
+                    ('\u{008A0}', '\u{008B4}'),
+                    ('\u{008B6}', '\u{008BD}'),
+                    ('\u{00904}', '\u{00928}'),
+                    ('\u{0092A}', '\u{00930}'),
+                    ('\u{00932}', '\u{00933}'),
+                    ('\u{00935}', '\u{00939}'),
+                    ('\u{0093D}', '\u{0093D}'),
+                    ('\u{00950}', '\u{00950}'),
+                    ('\u{00960}', '\u{00961}'),
+                    ('\u{00966}', '\u{0096F}'),
+                    ('\u{00972}', '\u{00980}'),
+                    ('\u{00985}', '\u{0098C}'),
+                    ('\u{0098F}', '\u{00990}'),
+                    ('\u{00993}', '\u{009A8}'),
+                    ('\u{009AA}', '\u{009B0}'),
+                    ('\u{009B2}', '\u{009B2}'),
+                    ('\u{009B6}', '\u{009B9}'),
+                    ('\u{009BD}', '\u{009BD}'),
+                    ('\u{009CE}', '\u{009CE}'),
+                    ('\u{009E0}', '\u{009E1}'),
+                    ('\u{009E6}', '\u{009F1}'),
+                    ('\u{009F4}', '\u{009F9}'),
+                    ('\u{009FC}', '\u{009FC}'),
+                    ('\u{00A05}', '\u{00A0A}'),
+                    ('\u{00A0F}', '\u{00A10}'),
+                    ('\u{00A13}', '\u{00A28}'),
+                    ('\u{00A2A}', '\u{00A30}'),
+                    ('\u{00A32}', '\u{00A32}'),
+                    ('\u{00A35}', '\u{00A35}'),
+                    ('\u{00A38}', '\u{00A39}'),
+                    ('\u{00A5C}', '\u{00A5C}'),
+                    ('\u{00A66}', '\u{00A6F}'),
+                    ('\u{00A72}', '\u{00A74}'),
+                    ('\u{00A85}', '\u{00A8D}'),
+                    ('\u{00A8F}', '\u{00A91}'),
+                    ('\u{00A93}', '\u{00AA8}'),
+                    ('\u{00AAA}', '\u{00AB0}'),
+                    ('\u{00AB2}', '\u{00AB3}'),
+                    ('\u{00AB5}', '\u{00AB9}'),
+                    ('\u{00ABD}', '\u{00ABD}'),
+                    ('\u{00AD0}', '\u{00AD0}'),
+                    ('\u{00AE0}', '\u{00AE1}'),
+                    ('\u{00AE6}', '\u{00AEF}'),
+                    ('\u{00AF9}', '\u{00AF9}'),
+                    ('\u{00B05}', '\u{00B0C}'),
+                    ('\u{00B0F}', '\u{00B10}'),
+                    ('\u{00B13}', '\u{00B28}'),
+                    ('\u{00B2A}', '\u{00B30}'),
+                    ('\u{00B32}', '\u{00B33}'),
+                    ('\u{00B35}', '\u{00B39}'),
+                    ('\u{00B3D}', '\u{00B3D}'),
+                    ('\u{00B5F}', '\u{00B61}'),
+                    ('\u{00B66}', '\u{00B6F}'),
+                    ('\u{00B71}', '\u{00B77}'),
+                    ('\u{00B83}', '\u{00B83}'),
+                    ('\u{00B85}', '\u{00B8A}'),
+                    ('\u{00B8E}', '\u{00B90}'),
+                    ('\u{00B92}', '\u{00B93}'),
+                    ('\u{00B95}', '\u{00B95}'),
+                    ('\u{00B99}', '\u{00B9A}'),
+                    ('\u{00B9C}', '\u{00B9C}'),
+                    ('\u{00B9E}', '\u{00B9F}'),
+                    ('\u{00BA3}', '\u{00BA4}'),
+                    ('\u{00BA8}', '\u{00BAA}'),
+                    ('\u{00BAE}', '\u{00BB9}'),
+                    ('\u{00BD0}', '\u{00BD0}'),
+                    ('\u{00BE6}', '\u{00BF2}'),
+                    ('\u{00C05}', '\u{00C0C}'),
+                    ('\u{00C0E}', '\u{00C10}'),
+                    ('\u{00C12}', '\u{00C28}'),
+                    ('\u{00C2A}', '\u{00C39}'),
+                    ('\u{00C3D}', '\u{00C3D}'),
+                    ('\u{00C58}', '\u{00C5A}'),
+                    ('\u{00C60}', '\u{00C61}'),
+                    ('\u{00C66}', '\u{00C6F}'),
+                    ('\u{00C78}', '\u{00C7E}'),
+                    ('\u{00C80}', '\u{00C80}'),
+                    ('\u{00C85}', '\u{00C8C}'),
+                    ('\u{00C8E}', '\u{00C90}'),
+                    ('\u{00C92}', '\u{00CA8}'),
+                    ('\u{00CAA}', '\u{00CB3}'),
+                    ('\u{00CB5}', '\u{00CB9}'),
+                    ('\u{00CBD}', '\u{00CBD}'),
+                    ('\u{00CDE}', '\u{00CDE}'),
+                    ('\u{00CE0}', '\u{00CE1}'),
+                    ('\u{00CE6}', '\u{00CEF}'),
+                    ('\u{00CF1}', '\u{00CF2}'),
+                    ('\u{00D05}', '\u{00D0C}'),
+                    ('\u{00D0E}', '\u{00D10}'),
+                    ('\u{00D12}', '\u{00D3A}'),
+                    ('\u{00D3D}', '\u{00D3D}'),
+                    ('\u{00D4E}', '\u{00D4E}'),
+                    ('\u{00D54}', '\u{00D56}'),
+                    ('\u{00D58}', '\u{00D61}'),
+                    ('\u{00D66}', '\u{00D78}'),
+                    ('\u{00D7A}', '\u{00D7F}'),
+                    ('\u{00D85}', '\u{00D96}'),
+                    ('\u{00D9A}', '\u{00DB1}'),
+                    ('\u{00DB3}', '\u{00DBB}'),
+                    ('\u{00DBD}', '\u{00DBD}'),
+                    ('\u{00DC0}', '\u{00DC6}'),
+                    ('\u{00DE6}', '\u{00DEF}'),
+                    ('\u{00E01}', '\u{00E30}'),
+                    ('\u{00E32}', '\u{00E32}'),
+                    ('\u{00E40}', '\u{00E45}'),
+                    ('\u{00E50}', '\u{00E59}'),
+                    ('\u{00E81}', '\u{00E82}'),
+                    ('\u{00E84}', '\u{00E84}'),
+                    ('\u{00E87}', '\u{00E88}'),
+                    ('\u{00E8A}', '\u{00E8A}'),
+                    ('\u{00E8D}', '\u{00E8D}'),
+                    ('\u{00E94}', '\u{00E97}'),
+                    ('\u{00E99}', '\u{00E9F}'),
+                    ('\u{00EA1}', '\u{00EA3}'),
+                    ('\u{00EA5}', '\u{00EA5}'),
+                    ('\u{00EA7}', '\u{00EA7}'),
+                    ('\u{00EAA}', '\u{00EAB}'),
+                    ('\u{00EAD}', '\u{00EB0}'),
+                    ('\u{00EB2}', '\u{00EB2}'),
+                    ('\u{00EBD}', '\u{00EBD}'),
+                    ('\u{00EC0}', '\u{00EC4}'),
+                    ('\u{00ED0}', '\u{00ED9}'),
+                    ('\u{00EDE}', '\u{00EDF}'),
+                    ('\u{00F00}', '\u{00F00}'),
+                    ('\u{00F20}', '\u{00F33}'),
+                    ('\u{00F40}', '\u{00F42}'),
+                    ('\u{00F44}', '\u{00F47}'),
+                    ('\u{00F49}', '\u{00F4C}'),
+                    ('\u{00F4E}', '\u{00F51}'),
+                    ('\u{00F53}', '\u{00F56}'),
+                    ('\u{00F58}', '\u{00F5B}'),
+                    ('\u{00F5D}', '\u{00F68}'),
+                    ('\u{00F6A}', '\u{00F6C}'),
+                    ('\u{00F88}', '\u{00F8C}'),
+                    ('\u{01000}', '\u{01025}'),
+                    ('\u{01027}', '\u{0102A}'),
+                    ('\u{0103F}', '\u{01049}'),
+                    ('\u{01050}', '\u{01055}'),
+                    ('\u{00030}', '\u{00037}'), // tail chars
+                ]),
+                OnesPad('='),
+                Repack,
+            )
+            .with_id(20)
+            .with_tail_bits(3),
+        ];
+        Box::new(encodings.into_iter().map(Arc::new).collect())
+    })
+}
@@ -0,0 +1,151 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use super::builder::EncodingBuilder;
+use super::{CharSpace, Encoding, Mode, PadType};
+use crate::error::BuildError;
+use crate::repack::BitOrder;
+
+/// Serializable description of an `Encoding`, so that charsets can be kept in data files and
+/// loaded with any serde format. Built-in encodings convert to it via `Encoding::definition()`,
+/// and it converts back via `Encoding::try_from()` (which validates it like `EncodingBuilder`).
+///
+/// In TOML:
+///
+/// ```
+/// use basehanja::{Encoding, EncodingDef};
+/// use std::convert::TryFrom;
+///
+/// let def: EncodingDef = toml::from_str(r#"
+///     name = "hex"
+///     long_name = "Hexadecimal"   # optional, defaults to `name`
+///     id = 1000                   # optional, for headers
+///     mode = "repack"             # optional: "repack" (default), "radix" or "block_radix"
+///     bit_order = "msb_first"     # optional: "msb_first" (default) or "lsb_first"
+///     pad = { block_pad = "?" }   # or drop_pad, omit_pad, ones_pad
+///
+///     [char_space]
+///     intervals = [["0", "9"], ["A", "F"]]   # or: concrete = "0123456789ABCDEF"
+/// "#).unwrap();
+/// let enc = Encoding::try_from(def).unwrap();
+/// assert_eq!("CAFE", enc.encode(&[0xca, 0xfe]));
+/// ```
+///
+/// The same in JSON:
+///
+/// ```json
+/// {
+///   "name": "hex",
+///   "long_name": "Hexadecimal",
+///   "id": 1000,
+///   "mode": "repack",
+///   "bit_order": "msb_first",
+///   "pad": { "block_pad": "?" },
+///   "char_space": { "intervals": [["0", "9"], ["A", "F"]] }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncodingDef {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u16>,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub bit_order: BitOrder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tail_bits: Option<u8>,
+    pub pad: PadType,
+    pub char_space: CharSpace,
+}
+
+impl Encoding {
+    pub fn definition(&self) -> EncodingDef {
+        EncodingDef {
+            name: self.name.clone(),
+            long_name: Some(self.long_name.clone()),
+            id: self.id,
+            mode: self.mode,
+            bit_order: self.bit_order,
+            tail_bits: self.tail_bits,
+            pad: self.pad_char,
+            char_space: self.char_space.clone(),
+        }
+    }
+}
+
+impl TryFrom<EncodingDef> for Encoding {
+    type Error = BuildError;
+
+    fn try_from(def: EncodingDef) -> Result<Self, Self::Error> {
+        let mut builder = EncodingBuilder::new(&def.name).pad(def.pad).mode(def.mode);
+        builder = builder.bit_order(def.bit_order);
+        if let Some(long_name) = &def.long_name {
+            builder = builder.long_name(long_name);
+        }
+        if let Some(id) = def.id {
+            builder = builder.id(id);
+        }
+        if let Some(bits) = def.tail_bits {
+            builder = builder.tail_bits(bits);
+        }
+        builder = match &def.char_space {
+            CharSpace::Concrete(s) => builder.alphabet(s),
+            CharSpace::Intervals(arr) => {
+                let arr = arr.iter().map(|&(a, b)| (a as u32, b as u32));
+                builder.intervals(&arr.collect::<Vec<(u32, u32)>>())
+            }
+        };
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EncodingDef;
+    use crate::encoding::{get_encodings, Encoding};
+    use crate::error::BuildError;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_builtins_roundtrip() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        for c in get_encodings() {
+            let def = c.definition();
+            let from_toml = toml::from_str(&toml::to_string(&def).unwrap()).unwrap();
+            let from_json = serde_json::from_str(&serde_json::to_string(&def).unwrap()).unwrap();
+            for other in [from_toml, from_json] {
+                assert_eq!(def, other, "Failed with `{}`", c.name);
+                let other = Encoding::try_from(other).unwrap();
+                assert_eq!(
+                    c.encode(&data),
+                    other.encode(&data),
+                    "Failed with `{}`",
+                    c.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_definition() {
+        let def: EncodingDef = serde_json::from_str(
+            r#"{"name": "x", "pad": {"drop_pad": "a"}, "char_space": {"concrete": "abc"}}"#,
+        )
+        .unwrap();
+        assert_eq!(None, def.long_name);
+        assert_eq!(
+            Some(BuildError::PadInAlphabet('a')),
+            Encoding::try_from(def).err()
+        );
+
+        let unknown_field = r#"{"name": "x", "pad": {"drop_pad": "="}, "charspace": {}}"#;
+        assert!(serde_json::from_str::<EncodingDef>(unknown_field).is_err());
+    }
+}
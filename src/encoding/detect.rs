@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use super::registry::Registry;
+use super::{Encoding, Mode};
+
+/// How much less likely a text is to come from an encoding whose `encode()` wouldn't have produced
+/// it (e.g. wrong padding, or set trailing bits)
+const NON_CANONICAL_PENALTY: f64 = 1e-3;
+
+/// `Registry::detect()` on the global registry
+pub fn detect(text: &str) -> Vec<(Arc<Encoding>, f64)> {
+    Registry::global().detect(text)
+}
+
+impl Registry {
+    /// Guess the encoding of `text`, ignoring whitespace. Returns the encodings that can decode it,
+    /// most likely first, along with confidences that add up to 1.
+    ///
+    /// Every char of a random encoded text is equally likely, so encodings with fewer chars are
+    /// likelier to produce a given text, e.g. `hex` wins over `base64` for `CAFE`. Encodings whose
+    /// `encode()` couldn't have produced the text (see `Encoding::decode_strict()`) are still
+    /// listed, but are much less likely.
+    ///
+    /// ```
+    /// use basehanja::detect;
+    ///
+    /// let found = detect("CAFE");
+    /// assert_eq!("hex", found[0].0.name);
+    /// assert_eq!("base64", found[1].0.name);
+    /// ```
+    pub fn detect(&self, text: &str) -> Vec<(Arc<Encoding>, f64)> {
+        let text = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        if text.is_empty() {
+            return vec![];
+        }
+
+        // log2 of the likelihood of every candidate
+        let mut acc = self
+            .encodings()
+            .into_iter()
+            .filter_map(|enc| {
+                let likelihood = enc.log_likelihood(&text)?;
+                Some((enc, likelihood))
+            })
+            .collect::<Vec<(Arc<Encoding>, f64)>>();
+        let max = acc.iter().map(|x| x.1).fold(f64::NEG_INFINITY, f64::max);
+        for x in acc.iter_mut() {
+            x.1 = (x.1 - max).exp2();
+        }
+        let total = acc.iter().map(|x| x.1).sum::<f64>();
+        for x in acc.iter_mut() {
+            x.1 /= total;
+        }
+        acc.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        acc
+    }
+}
+
+impl Encoding {
+    /// log2 of the likelihood of `text` being a random output of this encoding, or `None` if it
+    /// doesn't even decode
+    fn log_likelihood(&self, text: &str) -> Option<f64> {
+        // most encodings are ruled out by their alphabet alone, without decoding anything
+        let pad = *self.pad_char;
+        if !text
+            .chars()
+            .all(|c| c == pad || self.lookup.char_to_idx(c).is_some())
+        {
+            return None;
+        }
+        let canonical = match self.mode {
+            // decoding is quadratic, but can't fail past the alphabet. `encode()` writes a number,
+            // followed by a single pad char
+            Mode::Radix => self.deconcat(text).all(|(_, txt)| {
+                let unpadded = txt.trim_end_matches(pad);
+                !unpadded.is_empty() && txt.len() - unpadded.len() == pad.len_utf8()
+            }),
+            _ => {
+                self.decode(text).ok()?;
+                self.deconcat(text)
+                    .all(|(_, txt)| self.decode_strict(txt).is_ok())
+            }
+        };
+
+        // chars past the first 2^bitcount() are never produced in repack mode
+        let size = match self.mode {
+            Mode::Repack => (1u64 << self.bitcount()) as f64,
+            _ => self.num_chars as f64,
+        };
+        let nchars = text.chars().filter(|&c| c != *self.pad_char).count();
+        let penalty = match canonical {
+            true => 0.0,
+            false => NON_CANONICAL_PENALTY.log2(),
+        };
+        Some(-(nchars as f64) * size.log2() + penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::registry::Registry;
+
+    // not the global registry, its encodings might change
+    fn names(text: &str) -> Vec<String> {
+        let found = Registry::new().detect(text);
+        found.iter().map(|x| x.0.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        let b64 = ["base64", "base64url", "base64url-nopad"];
+        let b32 = ["base32", "base32hex"];
+        // base2048 has every ASCII letter, but its 11-bit chars make it the least likely
+        let b2048 = ["base2048"];
+        let hex_first = [&["hex"][..], &b64, &b32, &b2048].concat();
+        assert_eq!(hex_first, names("CAFE"));
+        assert_eq!(hex_first, names(" CA\nFE "));
+        let binary_first = [
+            &["binary", "hex", "base16"][..],
+            &b64,
+            &["base32hex"],
+            &b2048,
+        ]
+        .concat();
+        assert_eq!(binary_first, names("0110"));
+        assert_eq!([&b64[..], &b2048].concat(), names("YWFh"));
+        assert_eq!(
+            vec![
+                "base64",
+                "base64url",
+                "base32",
+                "base64url-nopad",
+                "base2048"
+            ],
+            names("YQ==YWE=")
+        );
+        assert_eq!([&["base16"][..], &b64, &b2048].concat(), names("cafe"));
+        assert_eq!(vec!["base64url", "base64url-nopad"], names("-_8="));
+        assert_eq!([&["base32"][..], &b64, &b2048].concat(), names("MZXW6YQ="));
+        assert_eq!(vec!["hiragana"], names("ぢぢ"));
+        assert_eq!("kanji", names("𠖫𠕊々")[0]);
+        assert_eq!("hangul-block", names("륉퐢쯁걡흐흐흐흐")[0]);
+        assert!(names("").is_empty());
+        assert!(names("~").is_empty());
+
+        let found = Registry::new().detect("CAFEBABE");
+        let total = found.iter().map(|x| x.1).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(found[0].1 > 0.99);
+    }
+
+    #[test]
+    fn test_non_canonical() {
+        let codec = "base64".parse::<&crate::encoding::Encoding>().unwrap();
+        let canonical = codec.log_likelihood("YQ==").unwrap();
+        // set trailing bits, missing padding
+        for text in &["YR==", "YQ"] {
+            let other = codec.log_likelihood(text).unwrap();
+            assert!(other < canonical + super::NON_CANONICAL_PENALTY.log2() + 1.0);
+        }
+        assert_eq!(None, codec.log_likelihood("Y-=="));
+
+        // radix mode tells canonical texts apart without decoding them
+        let codec = "kanji-radix".parse::<&crate::encoding::Encoding>().unwrap();
+        for text in &[
+            "一々",
+            "伀々一丁々",
+            "一",
+            "一々々",
+            "々",
+            "伀々々一々",
+            "一\u{4eee}",
+        ] {
+            let canonical = codec
+                .deconcat(text)
+                .all(|(_, txt)| codec.decode_strict(txt).is_ok());
+            let penalty = match canonical {
+                true => 0.0,
+                false => super::NON_CANONICAL_PENALTY.log2(),
+            };
+            let nchars = text.chars().filter(|&c| c != '々').count() as f64;
+            let size = (codec.char_space.num_chars() as f64).log2();
+            assert_eq!(Some(-nchars * size + penalty), codec.log_likelihood(text));
+        }
+        assert_eq!(None, codec.log_likelihood("一a々"));
+    }
+
+    #[test]
+    fn test_large_input() {
+        // would be quadratic if kanji-radix decoded it
+        let text = "伀".repeat(200_000) + "々";
+        assert!(names(&text).contains(&"kanji-radix".to_owned()));
+    }
+}
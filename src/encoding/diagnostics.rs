@@ -0,0 +1,128 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{CharSpace, Encoding};
+use crate::error::DecodeError;
+
+/// A problem found by `Encoding::diagnose()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic<'a> {
+    /// Positions in it count from the start of the whole input
+    pub error: DecodeError,
+    /// Index of the concatenated part that holds the problem
+    pub segment: usize,
+    /// The chars the encoding expects (besides its pad char)
+    pub expected: &'a CharSpace,
+}
+
+impl Encoding {
+    /// Every problem of `text`, instead of just the first one like `decode()` does. All invalid
+    /// chars are reported, in order. Concatenated parts without invalid chars are checked as a
+    /// whole, and report the error `decode()` would.
+    ///
+    /// ```
+    /// use basehanja::{DecodeError, Encoding};
+    ///
+    /// let kanji = "kanji".parse::<&Encoding>().unwrap();
+    /// let issues = kanji.diagnose("𠖫a々𠖫b");
+    /// let chars = issues.iter().map(|x| match x.error {
+    ///     DecodeError::InvalidChar {
+    ///         char,
+    ///         char_index,
+    ///         byte_offset,
+    ///     } => (char, char_index, byte_offset),
+    ///     _ => unreachable!(),
+    /// });
+    /// assert_eq!(vec![('a', 1, 4), ('b', 4, 12)], chars.collect::<Vec<_>>());
+    /// assert_eq!(vec![0, 1], issues.iter().map(|x| x.segment).collect::<Vec<_>>());
+    /// ```
+    pub fn diagnose(&self, text: &str) -> Vec<Diagnostic<'_>> {
+        let mut acc = vec![];
+        let mut char_offset = 0;
+        for (segment, (byte_offset, txt)) in self.deconcat(text).enumerate() {
+            let issue = |error| Diagnostic {
+                error,
+                segment,
+                expected: &self.char_space,
+            };
+            let unpadded = txt.trim_end_matches(*self.pad_char);
+            let len = acc.len();
+            for (i, (j, c)) in unpadded.char_indices().enumerate() {
+                if self.lookup.char_to_idx(c).is_none() {
+                    acc.push(issue(DecodeError::InvalidChar {
+                        char: c,
+                        char_index: char_offset + i,
+                        byte_offset: byte_offset + j,
+                    }));
+                }
+            }
+            if acc.len() == len {
+                let mut buf = vec![0; self.max_decoded_len(txt)];
+                if let Err(e) = self.decode_single(txt, char_offset, byte_offset, &mut buf) {
+                    acc.push(issue(e));
+                }
+            }
+            char_offset += txt.chars().count();
+        }
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::Encoding;
+    use crate::error::DecodeError;
+
+    #[test]
+    fn test_diagnose() {
+        let codec = "base64".parse::<&Encoding>().unwrap();
+        assert!(codec.diagnose("YQ==YWE=").is_empty());
+        assert!(codec.diagnose("").is_empty());
+
+        let issues = codec.diagnose("Y-==Y?-h=");
+        let found = issues
+            .iter()
+            .map(|x| (x.segment, x.error.clone()))
+            .collect::<Vec<_>>();
+        let invalid = |char, char_index| DecodeError::InvalidChar {
+            char,
+            char_index,
+            byte_offset: char_index,
+        };
+        assert_eq!(
+            vec![
+                (0, invalid('-', 1)),
+                (1, invalid('?', 5)),
+                (1, invalid('-', 6)),
+            ],
+            found
+        );
+        assert!(issues
+            .iter()
+            .all(|x| std::ptr::eq(x.expected, &codec.char_space)));
+
+        // the first error is the one `decode()` reports
+        for text in &["Y-==Y?-h=", "YQ==Y?"] {
+            assert_eq!(
+                codec.decode(text).unwrap_err(),
+                codec.diagnose(text)[0].error,
+                "`{}`",
+                text
+            );
+        }
+
+        let codec = "hangul-block".parse::<&Encoding>().unwrap();
+        let issues = codec.diagnose("흏흏흏륉퐢쯁흐걡x흐흐흐흐");
+        assert_eq!(
+            vec![
+                DecodeError::InvalidBlock { char_index: 0 },
+                DecodeError::InvalidChar {
+                    char: 'x',
+                    char_index: 8,
+                    byte_offset: 24,
+                },
+            ],
+            issues.into_iter().map(|x| x.error).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+#[cfg(feature = "std")]
+use super::registry::Registry;
+use super::Encoding;
+#[cfg(feature = "std")]
+use crate::error::DecodeError;
+
+/// Format version written in headers
+pub const HEADER_VERSION: u8 = 1;
+
+/// Encoding ids must be below this
+pub const MAX_ENCODING_ID: u16 = 1 << 12;
+
+/// Ids below this are reserved for the built-in encodings
+pub const MIN_CUSTOM_ID: u16 = 256;
+
+/// Headers are a single char of the Supplementary Private Use Area-A (U+F0000-U+FFFFD), holding the
+/// version in its upper 4 bits and the id in the lower 12
+const MARKER_BASE: u32 = 0xF0000;
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+const MARKER_LAST: u32 = 0xFFFFD;
+
+/// `None` past `MARKER_LAST`, i.e. for ids above 4093 in version 15, or versions above 15
+fn marker(version: u8, id: u16) -> Option<char> {
+    let u = MARKER_BASE + ((version as u32) << 12) + id as u32;
+    match version < 16 && id < MAX_ENCODING_ID && u <= MARKER_LAST {
+        true => char::from_u32(u),
+        false => None,
+    }
+}
+
+/// `(version, id)` of a header char
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+fn parse_marker(c: char) -> Option<(u8, u16)> {
+    let u = c as u32;
+    if !(MARKER_BASE..=MARKER_LAST).contains(&u) {
+        return None;
+    }
+    let u = u - MARKER_BASE;
+    Some(((u >> 12) as u8, (u as u16) % MAX_ENCODING_ID))
+}
+
+impl Encoding {
+    /// `encode()`, preceded by a header naming this encoding (by its id) and the format version,
+    /// so that `Registry::decode_any()` can decode it without being told the encoding. `None` if
+    /// the encoding has no id.
+    ///
+    /// ```
+    /// use basehanja::{Encoding, Registry};
+    ///
+    /// let kanji = "kanji".parse::<&Encoding>().unwrap();
+    /// let text = kanji.encode_with_header(b"aa").unwrap();
+    /// let (enc, data) = Registry::global().decode_any(&text).unwrap();
+    /// assert_eq!("kanji", enc.name);
+    /// assert_eq!(b"aa".to_vec(), data);
+    /// ```
+    pub fn encode_with_header(&self, bytes: &[u8]) -> Option<String> {
+        let header = marker(HEADER_VERSION, self.id?)?;
+        let mut s = String::with_capacity(header.len_utf8());
+        s.push(header);
+        self.encode_into(bytes, &mut s);
+        Some(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Registry {
+    /// Decode a text produced by `Encoding::encode_with_header()`, with the encoding its header
+    /// names. Error positions count the header too.
+    pub fn decode_any(&self, text: &str) -> Result<(Arc<Encoding>, Vec<u8>), DecodeError> {
+        let header = text.chars().next().ok_or(DecodeError::MissingHeader)?;
+        let (version, id) = parse_marker(header).ok_or(DecodeError::MissingHeader)?;
+        if version != HEADER_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let enc = self.get_by_id(id).ok_or(DecodeError::UnknownId(id))?;
+        let data = enc
+            .decode(&text[header.len_utf8()..])
+            .map_err(|e| e.offset(1, header.len_utf8()))?;
+        Ok((enc, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use super::HEADER_VERSION;
+    use super::{marker, parse_marker, MAX_ENCODING_ID};
+    #[cfg(feature = "std")]
+    use crate::encoding::builder::EncodingBuilder;
+    #[cfg(feature = "std")]
+    use crate::encoding::registry::Registry;
+    #[cfg(feature = "std")]
+    use crate::encoding::{get_encodings, PadType};
+    #[cfg(feature = "std")]
+    use crate::error::DecodeError;
+    #[cfg(feature = "std")]
+    use std::sync::Arc;
+
+    #[test]
+    fn test_marker() {
+        assert_eq!(Some('\u{f1003}'), marker(1, 3));
+        for &(v, id) in &[(0, 0), (1, 9), (14, MAX_ENCODING_ID - 1), (15, 4093)] {
+            assert_eq!(Some((v, id)), parse_marker(marker(v, id).unwrap()));
+        }
+        // past U+FFFFD, parse_marker() would reject them
+        for &(v, id) in &[
+            (15, 4094),
+            (15, MAX_ENCODING_ID - 1),
+            (16, 0),
+            (1, MAX_ENCODING_ID),
+        ] {
+            assert_eq!(None, marker(v, id));
+        }
+        assert_eq!(None, parse_marker('\u{effff}'));
+        assert_eq!(None, parse_marker('a'));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_any() {
+        let reg = Registry::new();
+        let data = b"mixed archive";
+        for c in get_encodings() {
+            let text = c.encode_with_header(data).unwrap();
+            let (enc, dec) = reg.decode_any(&text).unwrap();
+            assert!(Arc::ptr_eq(c, &enc));
+            assert_eq!(data.to_vec(), dec);
+        }
+
+        let custom = EncodingBuilder::new("octal")
+            .alphabet("01234567")
+            .pad(PadType::DropPad('='))
+            .build()
+            .unwrap();
+        assert_eq!(None, custom.encode_with_header(data));
+        let custom = EncodingBuilder::new("octal")
+            .alphabet("01234567")
+            .pad(PadType::DropPad('='))
+            .id(300)
+            .build()
+            .unwrap();
+        let text = custom.encode_with_header(data).unwrap();
+        assert_eq!(
+            Some(DecodeError::UnknownId(300)),
+            reg.decode_any(&text).err()
+        );
+        reg.register(custom).unwrap();
+        assert_eq!(data.to_vec(), reg.decode_any(&text).unwrap().1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_any_errors() {
+        let reg = Registry::new();
+        assert_eq!(Some(DecodeError::MissingHeader), reg.decode_any("").err());
+        assert_eq!(
+            Some(DecodeError::MissingHeader),
+            reg.decode_any("YQ==").err()
+        );
+        let text = format!("{}YQ==", marker(HEADER_VERSION + 1, 3).unwrap());
+        assert_eq!(
+            Some(DecodeError::UnsupportedVersion(HEADER_VERSION + 1)),
+            reg.decode_any(&text).err()
+        );
+        let text = format!("{}YQ-=", marker(HEADER_VERSION, 3).unwrap());
+        assert_eq!(
+            Some(DecodeError::InvalidChar {
+                char: '-',
+                char_index: 3,
+                byte_offset: 6,
+            }),
+            reg.decode_any(&text).err()
+        );
+    }
+}
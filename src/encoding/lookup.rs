@@ -0,0 +1,248 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use super::CharSpace;
+use crate::repack::uVar;
+
+/// Precomputed index <-> char lookups of a `CharSpace`, so that neither direction has to walk the
+/// whole charset
+pub enum Lookup {
+    /// every char by its index, and every index by its char
+    Concrete(Vec<char>, HashMap<char, uVar>),
+    /// `(first index, first char, last char)` of every interval in charset order (i.e. the prefix
+    /// sums of their lengths), and the positions of the same intervals when sorted by first char
+    Intervals(Vec<(uVar, char, char)>, Vec<usize>),
+}
+
+impl Lookup {
+    pub fn new(char_space: &CharSpace) -> Lookup {
+        match char_space {
+            CharSpace::Concrete(s) => {
+                let chars = s.chars().collect::<Vec<char>>();
+                let mut idxs = HashMap::new();
+                for (i, &c) in chars.iter().enumerate() {
+                    idxs.entry(c).or_insert(i as uVar);
+                }
+                Lookup::Concrete(chars, idxs)
+            }
+            CharSpace::Intervals(arr) => {
+                let mut first_idx = 0;
+                let mut by_idx = Vec::with_capacity(arr.len());
+                for &(a, b) in arr.iter() {
+                    by_idx.push((first_idx, a, b));
+                    first_idx += (b as uVar) - (a as uVar) + 1;
+                }
+                let mut by_char = (0..by_idx.len()).collect::<Vec<usize>>();
+                by_char.sort_by_key(|&i| by_idx[i].1);
+                Lookup::Intervals(by_idx, by_char)
+            }
+        }
+    }
+
+    pub fn idx_to_char(&self, u: uVar) -> char {
+        match self {
+            Lookup::Concrete(chars, _) => chars[u as usize],
+            Lookup::Intervals(by_idx, _) => {
+                let i = by_idx.partition_point(|x| x.0 <= u) - 1;
+                let (first_idx, a, _) = by_idx[i];
+                char::from_u32(a as uVar + (u - first_idx)).unwrap()
+            }
+        }
+    }
+
+    pub fn char_to_idx(&self, c: char) -> Option<uVar> {
+        match self {
+            Lookup::Concrete(_, idxs) => idxs.get(&c).copied(),
+            Lookup::Intervals(by_idx, by_char) => {
+                let i = by_char.partition_point(|&i| by_idx[i].1 <= c);
+                let (first_idx, a, b) = by_idx[by_char[i.checked_sub(1)?]];
+                if c > b {
+                    return None;
+                }
+                Some(first_idx + (c as uVar - a as uVar))
+            }
+        }
+    }
+}
+
+/// Byte tables of alphabets made only of ASCII chars, for the word-at-a-time paths
+pub struct AsciiLookup {
+    /// every char by its index
+    chars: Vec<u8>,
+    /// every index by its char, or `INVALID` (also for non-ASCII bytes)
+    idxs: [u8; 256],
+    /// `(first char, last char, first index)` of every interval, if the alphabet is made of few
+    /// enough of them for the SIMD translation
+    #[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+    intervals: Option<Vec<(u8, u8, u8)>>,
+}
+
+const INVALID: u8 = 0xFF;
+
+impl AsciiLookup {
+    /// `None` unless every char of the charset is ASCII
+    pub fn new(char_space: &CharSpace) -> Option<AsciiLookup> {
+        // checked before listing the chars, non-ASCII alphabets can be huge
+        let (chars, intervals) = match char_space {
+            CharSpace::Concrete(s) if !s.is_ascii() => return None,
+            CharSpace::Concrete(s) => (s.chars().collect::<Vec<char>>(), None),
+            CharSpace::Intervals(arr) if arr.iter().any(|x| !x.0.is_ascii() || !x.1.is_ascii()) => {
+                return None
+            }
+            CharSpace::Intervals(arr) => {
+                let chars = arr.iter().flat_map(|&(a, b)| a..=b).collect::<Vec<char>>();
+                let mut first_idx = 0;
+                let mut acc = Vec::with_capacity(arr.len());
+                for &(a, b) in arr.iter() {
+                    acc.push((a as u8, b as u8, first_idx as u8));
+                    first_idx += (b as usize) - (a as usize) + 1;
+                }
+                // DEL would overflow the signed comparisons
+                (
+                    chars,
+                    Some(acc).filter(|x| x.len() <= 8 && x.iter().all(|y| y.1 < 0x7F)),
+                )
+            }
+        };
+        let mut idxs = [INVALID; 256];
+        for (i, &c) in chars.iter().enumerate() {
+            // repeated chars (thus overlapping intervals) are left to `Lookup`
+            if idxs[c as usize] != INVALID {
+                return None;
+            }
+            idxs[c as usize] = i as u8;
+        }
+        Some(AsciiLookup {
+            chars: chars.into_iter().map(|c| c as u8).collect(),
+            idxs,
+            intervals,
+        })
+    }
+
+    pub fn idx_to_char(&self, u: uVar) -> char {
+        self.chars[u as usize] as char
+    }
+
+    /// Write the indices of the chars of `text` to `out`, up to the first char that isn't in the
+    /// alphabet. Returns how many were written.
+    pub fn translate(&self, text: &[u8], out: &mut [u8]) -> usize {
+        let len = core::cmp::min(text.len(), out.len());
+        let (text, out) = (&text[..len], &mut out[..len]);
+        #[cfg(target_arch = "x86_64")]
+        let done = match &self.intervals {
+            Some(intervals) => sse2::translate(intervals, text, out),
+            None => 0,
+        };
+        #[cfg(not(target_arch = "x86_64"))]
+        let done = 0;
+        for (i, (&c, x)) in text[done..].iter().zip(out[done..].iter_mut()).enumerate() {
+            *x = self.idxs[c as usize];
+            if *x == INVALID {
+                return done + i;
+            }
+        }
+        len
+    }
+}
+
+/// SSE2 is part of x86_64, so no runtime detection is needed. There's no NEON or wasm-simd128
+/// equivalent on purpose: the scalar table lookup is what every other target uses.
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use core::arch::x86_64::*;
+
+    /// `AsciiLookup::translate()` 16 chars at a time, for interval alphabets. Stops before the
+    /// first 16 chars that hold an invalid one.
+    pub fn translate(intervals: &[(u8, u8, u8)], text: &[u8], out: &mut [u8]) -> usize {
+        let mut done = 0;
+        for (src, dst) in text.chunks_exact(16).zip(out.chunks_exact_mut(16)) {
+            // SAFETY: SSE2 is always available on x86_64, and the loads and stores are unaligned
+            // ones of exactly 16 bytes
+            unsafe {
+                let c = _mm_loadu_si128(src.as_ptr() as *const __m128i);
+                let mut idx = _mm_setzero_si128();
+                let mut valid = _mm_setzero_si128();
+                for &(a, b, first) in intervals {
+                    // non-ASCII bytes are negative, thus below every interval
+                    let above = _mm_cmpgt_epi8(c, _mm_set1_epi8(a as i8 - 1));
+                    let below = _mm_cmplt_epi8(c, _mm_set1_epi8(b as i8 + 1));
+                    let inside = _mm_and_si128(above, below);
+                    let shift = _mm_set1_epi8(first.wrapping_sub(a) as i8);
+                    idx = _mm_or_si128(idx, _mm_and_si128(inside, _mm_add_epi8(c, shift)));
+                    valid = _mm_or_si128(valid, inside);
+                }
+                if _mm_movemask_epi8(valid) != 0xFFFF {
+                    break;
+                }
+                _mm_storeu_si128(dst.as_mut_ptr() as *mut __m128i, idx);
+            }
+            done += 16;
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::get_encodings;
+
+    #[test]
+    fn test_lookup_roundtrip() {
+        for c in get_encodings() {
+            let len = c.char_space.num_chars();
+            let mut prev = None;
+            for u in 0..len as u32 {
+                let ch = c.lookup.idx_to_char(u);
+                assert_eq!(
+                    Some(u),
+                    c.lookup.char_to_idx(ch),
+                    "Failed with `{}`",
+                    c.name
+                );
+                assert_ne!(prev, Some(ch), "Failed with `{}`", c.name);
+                prev = Some(ch);
+            }
+            assert_eq!(
+                None,
+                c.lookup.char_to_idx(*c.pad_char),
+                "Failed with `{}`",
+                c.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_ascii_lookup() {
+        use super::AsciiLookup;
+        use crate::encoding::CharSpace;
+        let ascii = |name: &str| {
+            let c = get_encodings().iter().find(|x| x.name == name).unwrap();
+            AsciiLookup::new(&c.char_space).is_some()
+        };
+        assert!(ascii("hex") && ascii("base64") && ascii("binary"));
+        assert!(!ascii("kanji") && !ascii("base2048") && !ascii("hiragana"));
+        // the whole of Unicode, turned down from its ends
+        let all = CharSpace::Intervals(vec![('\0', '\u{D7FF}'), ('\u{E000}', char::MAX)]);
+        assert!(AsciiLookup::new(&all).is_none());
+    }
+
+    #[test]
+    fn test_lookup_outside() {
+        let c = get_encodings().iter().find(|x| x.name == "kanji").unwrap();
+        for ch in &[
+            '\0',
+            '\u{33ff}',
+            '\u{3db6}',
+            '\u{4dff}',
+            '\u{a000}',
+            '\u{2a6e0}',
+        ] {
+            assert_eq!(None, c.lookup.char_to_idx(*ch));
+        }
+        assert_eq!(Some(20992), c.lookup.char_to_idx('\u{3400}'));
+        assert_eq!('\u{3400}', c.lookup.idx_to_char(20992));
+    }
+}
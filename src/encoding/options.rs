@@ -0,0 +1,301 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use unicode_width::UnicodeWidthChar;
+
+use super::Encoding;
+use crate::error::DecodeError;
+
+/// Invisible chars that chat apps and mail clients like to insert: zero-width (non-)joiners and
+/// spaces, word joiners, soft hyphens and byte order marks
+const INVISIBLE: &[char] = &[
+    '\u{00AD}', '\u{180E}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}',
+];
+
+/// Chars that `DecodeOptions` skips. Chars of the alphabet, and the pad char, are never skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Ignore {
+    /// skip nothing, like `Encoding::decode`
+    #[default]
+    Nothing,
+    /// skip whitespace (line breaks and U+3000 included) and the invisible chars in `INVISIBLE`
+    Whitespace,
+    /// skip the given chars
+    Chars(Vec<char>),
+    /// skip everything that isn't part of the encoding
+    NotInAlphabet,
+}
+
+/// Lenient decoding, see `Encoding::decode_with()`
+///
+/// ```
+/// use basehanja::{DecodeOptions, Encoding, Ignore};
+///
+/// let hex = "hex".parse::<&Encoding>().unwrap();
+/// let opts = DecodeOptions::new().ignore(Ignore::Whitespace).fold_case(true);
+/// assert_eq!(vec![0xca, 0xfe], hex.decode_with("ca\n fe", &opts).unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecodeOptions {
+    ignore: Ignore,
+    fold_case: bool,
+}
+
+impl DecodeOptions {
+    /// Strict options, same as `Encoding::decode`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skip whitespace and invisible chars, and fold case
+    pub fn lenient() -> Self {
+        Self::new().ignore(Ignore::Whitespace).fold_case(true)
+    }
+
+    pub fn ignore(mut self, ignore: Ignore) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    /// Accept chars of the other case (e.g. `a-f` for hex), if only one of the cases belongs to the
+    /// alphabet
+    pub fn fold_case(mut self, fold_case: bool) -> Self {
+        self.fold_case = fold_case;
+        self
+    }
+
+    /// What `c` stands for, or `None` if it's skipped
+    fn filter(&self, enc: &Encoding, c: char) -> Option<char> {
+        let known = |c: char| c == *enc.pad_char || enc.lookup.char_to_idx(c).is_some();
+        if known(c) {
+            return Some(c);
+        }
+        let folded = match self.fold_case {
+            true => other_case(c).filter(|&x| known(x)),
+            false => None,
+        };
+        let skip = match &self.ignore {
+            Ignore::Nothing => false,
+            Ignore::Whitespace => c.is_whitespace() || INVISIBLE.contains(&c),
+            Ignore::Chars(arr) => arr.contains(&c),
+            Ignore::NotInAlphabet => folded.is_none(),
+        };
+        match skip {
+            true => None,
+            false => Some(folded.unwrap_or(c)),
+        }
+    }
+}
+
+/// The only char of the other case of `c`, if any
+fn other_case(c: char) -> Option<char> {
+    let mut it = match c.is_lowercase() {
+        true => c.to_uppercase().collect::<Vec<char>>(),
+        false => c.to_lowercase().collect(),
+    }
+    .into_iter();
+    match (it.next(), it.next()) {
+        (Some(x), None) if x != c => Some(x),
+        _ => None,
+    }
+}
+
+/// Where to break the lines of the output, see `EncodeOptions`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    /// at most this many chars per line
+    Chars(usize),
+    /// at most this many terminal columns per line. CJK chars are 2 columns wide
+    Columns(usize),
+}
+
+/// Formatting of the encoded text, see `Encoding::encode_with()`
+///
+/// ```
+/// use basehanja::{EncodeOptions, Encoding, Wrap};
+///
+/// let kanji = "kanji".parse::<&Encoding>().unwrap();
+/// let opts = EncodeOptions::new().wrap(Wrap::Columns(4));
+/// assert_eq!("𠖫𠖫\n𠖫", kanji.encode_with(b"aaaaaa", &opts));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodeOptions {
+    wrap: Option<Wrap>,
+    line_break: String,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            wrap: None,
+            line_break: "\n".to_owned(),
+        }
+    }
+}
+
+impl EncodeOptions {
+    /// No wrapping, same as `Encoding::encode`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = Some(wrap);
+        self
+    }
+
+    /// Line separator of the wrapped output. Defaults to `"\n"`
+    pub fn line_break(mut self, line_break: &str) -> Self {
+        self.line_break = line_break.to_owned();
+        self
+    }
+}
+
+impl Encoding {
+    /// `decode()`, but skipping or folding the chars picked by `opts`. Error positions refer to
+    /// `text` itself.
+    pub fn decode_with(&self, text: &str, opts: &DecodeOptions) -> Result<Vec<u8>, DecodeError> {
+        if *opts == DecodeOptions::default() {
+            return self.decode(text);
+        }
+        let filtered = text
+            .chars()
+            .filter_map(|c| opts.filter(self, c))
+            .collect::<String>();
+        self.decode(&filtered)
+            .map_err(|e| self.remap_error(e, text, opts))
+    }
+
+    /// `encode()`, formatted according to `opts`. Lines never end with a break.
+    pub fn encode_with(&self, bytes: &[u8], opts: &EncodeOptions) -> String {
+        let text = self.encode(bytes);
+        let (limit, by_columns) = match opts.wrap {
+            None => return text,
+            Some(Wrap::Chars(n)) => (n, false),
+            Some(Wrap::Columns(n)) => (n, true),
+        };
+
+        let mut acc = String::with_capacity(text.len());
+        let mut line = 0;
+        for c in text.chars() {
+            let width = match by_columns {
+                true => c.width().unwrap_or(0),
+                false => 1,
+            };
+            // a char wider than the limit still gets a line of its own
+            if line > 0 && line + width > limit {
+                acc += &opts.line_break;
+                line = 0;
+            }
+            acc.push(c);
+            line += width;
+        }
+        acc
+    }
+
+    /// Make the positions of an error on the filtered text refer to the original `text`
+    fn remap_error(&self, err: DecodeError, text: &str, opts: &DecodeOptions) -> DecodeError {
+        // (char index, byte offset, char) of the `idx`-th kept char
+        let find = |idx: usize| {
+            text.char_indices()
+                .enumerate()
+                .filter(|(_, (_, c))| opts.filter(self, *c).is_some())
+                .nth(idx)
+                .map(|(i, (j, c))| (i, j, c))
+                .unwrap()
+        };
+        match err {
+            DecodeError::InvalidChar { char_index, .. } => {
+                let (char_index, byte_offset, char) = find(char_index);
+                DecodeError::InvalidChar {
+                    char,
+                    char_index,
+                    byte_offset,
+                }
+            }
+            DecodeError::InvalidBlock { char_index } => DecodeError::InvalidBlock {
+                char_index: find(char_index).0,
+            },
+            e => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeOptions, EncodeOptions, Ignore, Wrap};
+    use crate::encoding::{get_encodings, Encoding};
+    use crate::error::DecodeError;
+
+    #[test]
+    fn test_lenient_decode() {
+        let opts = DecodeOptions::lenient();
+        let hex = "hex".parse::<&Encoding>().unwrap();
+        assert_eq!(vec![0xca, 0xfe], hex.decode_with("cAfE", &opts).unwrap());
+        assert!(hex
+            .decode_with("cafe", &DecodeOptions::new().ignore(Ignore::Whitespace))
+            .is_err());
+
+        let kanji = "kanji".parse::<&Encoding>().unwrap();
+        let pasted = "\u{feff}𠖫\r\n𠖫\u{3000}𠖫\u{200b}𠕊\u{ad}々\n";
+        assert_eq!(
+            b"aaaaaaa".to_vec(),
+            kanji.decode_with(pasted, &opts).unwrap()
+        );
+
+        let opts = DecodeOptions::new().ignore(Ignore::Chars(vec!['-', '_']));
+        assert_eq!(
+            b"aaaa".to_vec(),
+            kanji.decode_with("𠖫-_𠖫", &opts).unwrap()
+        );
+
+        let opts = DecodeOptions::new().ignore(Ignore::NotInAlphabet);
+        let base64 = "base64".parse::<&Encoding>().unwrap();
+        assert_eq!(
+            b"aaa".to_vec(),
+            base64.decode_with("<YW.Fh>", &opts).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let opts = DecodeOptions::lenient();
+        let base64 = "base64".parse::<&Encoding>().unwrap();
+        assert_eq!(
+            Err(DecodeError::InvalidChar {
+                char: '-',
+                char_index: 7,
+                byte_offset: 9,
+            }),
+            base64.decode_with("YQ==\u{3000}YW-h", &opts)
+        );
+        let block = "hangul-block".parse::<&Encoding>().unwrap();
+        assert_eq!(
+            Err(DecodeError::InvalidBlock { char_index: 6 }),
+            block.decode_with("륉퐢\n쯁\n\n흏흏흏", &opts)
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        for c in get_encodings() {
+            for wrap in &[Wrap::Chars(7), Wrap::Columns(7), Wrap::Columns(1)] {
+                let opts = EncodeOptions::new().wrap(*wrap).line_break("\r\n");
+                let text = c.encode_with(&data, &opts);
+                for line in text.split("\r\n") {
+                    let len = match wrap {
+                        Wrap::Chars(_) => line.chars().count(),
+                        Wrap::Columns(_) => unicode_width::UnicodeWidthStr::width(line),
+                    };
+                    let limit = match wrap {
+                        Wrap::Chars(n) | Wrap::Columns(n) => *n,
+                    };
+                    assert!(len <= limit || line.chars().count() == 1);
+                }
+                let dec = c.decode_with(&text, &DecodeOptions::lenient()).unwrap();
+                assert_eq!(data, dec, "Failed with `{}`", c.name);
+            }
+        }
+    }
+}
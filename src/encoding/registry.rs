@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use super::{get_encodings, Encoding};
+use crate::error::{DecodeError, RegistryError};
+
+static GLOBAL: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Never changed, for `FromStr for &Encoding`
+static BUILTINS: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Default aliases of the built-in encodings
+static BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("hexadecimal", "hex"),
+    ("hanzi", "kanji"),
+    ("hanja", "kanji"),
+];
+
+/// Thread-safe set of encodings, searchable by name or alias. Names are case and whitespace
+/// insensitive.
+///
+/// Encodings are handed out as `Arc`s: an unregistered encoding is freed once the last of them is
+/// dropped.
+pub struct Registry {
+    inner: RwLock<Inner>,
+}
+
+struct Inner {
+    /// normalized name, and encoding
+    encodings: Vec<(String, Arc<Encoding>)>,
+    /// normalized alias -> normalized name
+    aliases: HashMap<String, String>,
+}
+
+impl Registry {
+    /// A registry holding the built-in encodings, and their aliases
+    pub fn new() -> Registry {
+        let aliases = BUILTIN_ALIASES
+            .iter()
+            .map(|(a, b)| (normalize(a), normalize(b)))
+            .collect();
+        let encodings = get_encodings()
+            .iter()
+            .map(|x| (normalize(&x.name), x.clone()))
+            .collect();
+        Registry {
+            inner: RwLock::new(Inner { encodings, aliases }),
+        }
+    }
+
+    /// The registry used by the name-based functions of this crate
+    pub fn global() -> &'static Registry {
+        &GLOBAL
+    }
+
+    /// A built-in encoding, looked up like `get()` in a registry that was never changed
+    pub(crate) fn builtin(name: &str) -> Result<&'static Encoding, DecodeError> {
+        let found = BUILTINS.get(name)?;
+        let builtin = get_encodings().iter().find(|x| Arc::ptr_eq(x, &found));
+        Ok(builtin.unwrap())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<Encoding>, DecodeError> {
+        let inner = self.inner.read().unwrap();
+        let key = normalize(name);
+        inner
+            .find(&key)
+            .ok_or_else(|| DecodeError::UnknownEncoding {
+                name: name.trim().to_owned(),
+                suggestion: inner.suggest(&key),
+            })
+    }
+
+    pub fn get_by_id(&self, id: u16) -> Option<Arc<Encoding>> {
+        self.inner.read().unwrap().find_id(id)
+    }
+
+    /// All encodings, in registration order
+    pub fn encodings(&self) -> Vec<Arc<Encoding>> {
+        let inner = self.inner.read().unwrap();
+        inner.encodings.iter().map(|x| x.1.clone()).collect()
+    }
+
+    pub fn register(&self, encoding: Encoding) -> Result<Arc<Encoding>, RegistryError> {
+        let mut inner = self.inner.write().unwrap();
+        let key = normalize(&encoding.name);
+        if inner.find(&key).is_some() {
+            return Err(RegistryError::NameTaken {
+                name: encoding.name,
+            });
+        }
+        if let Some(id) = encoding.id.filter(|&x| inner.find_id(x).is_some()) {
+            return Err(RegistryError::IdTaken { id });
+        }
+        let encoding = Arc::new(encoding);
+        inner.encodings.push((key, encoding.clone()));
+        Ok(encoding)
+    }
+
+    /// Remove an encoding (looked up by name or alias), along with its aliases
+    pub fn unregister(&self, name: &str) -> Option<Arc<Encoding>> {
+        let mut inner = self.inner.write().unwrap();
+        let key = inner.resolve(&normalize(name)).to_owned();
+        let i = inner.encodings.iter().position(|x| x.0 == key)?;
+        let (key, encoding) = inner.encodings.remove(i);
+        inner.aliases.retain(|_, v| *v != key);
+        Some(encoding)
+    }
+
+    /// Make `alias` resolve to the encoding named `name`
+    pub fn add_alias(&self, alias: &str, name: &str) -> Result<(), RegistryError> {
+        let mut inner = self.inner.write().unwrap();
+        let alias_key = normalize(alias);
+        if inner.find(&alias_key).is_some() {
+            return Err(RegistryError::NameTaken {
+                name: alias.trim().to_owned(),
+            });
+        }
+        let target_key = inner.resolve(&normalize(name)).to_owned();
+        if inner.find(&target_key).is_none() {
+            return Err(RegistryError::UnknownEncoding {
+                name: name.trim().to_owned(),
+            });
+        }
+        inner.aliases.insert(alias_key, target_key);
+        Ok(())
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inner {
+    /// The normalized name `key` stands for, if it's an alias
+    fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        self.aliases.get(key).map_or(key, |x| x.as_str())
+    }
+
+    fn find(&self, key: &str) -> Option<Arc<Encoding>> {
+        let key = self.resolve(key);
+        let found = self.encodings.iter().find(|x| x.0 == key);
+        found.map(|x| x.1.clone())
+    }
+
+    fn find_id(&self, id: u16) -> Option<Arc<Encoding>> {
+        let found = self.encodings.iter().find(|x| x.1.id == Some(id));
+        found.map(|x| x.1.clone())
+    }
+
+    /// The closest name or alias to `key`, unless they are all too different
+    fn suggest(&self, key: &str) -> Option<String> {
+        let names = self
+            .encodings
+            .iter()
+            .map(|x| (x.0.as_str(), x.1.name.as_str()));
+        let aliases = self.aliases.keys().map(|x| (x.as_str(), x.as_str()));
+        names
+            .chain(aliases)
+            .map(|(k, x)| (edit_distance(key, k), x))
+            .filter(|(d, _)| *d <= std::cmp::max(2, key.chars().count() / 3))
+            .min()
+            .map(|(_, x)| x.to_owned())
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut prev = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut crt = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let subst = prev[j] + (ca != *cb) as usize;
+            crt[j + 1] = *[subst, prev[j + 1] + 1, crt[j] + 1].iter().min().unwrap();
+        }
+        prev = crt;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Registry;
+    use crate::encoding::builder::EncodingBuilder;
+    use crate::encoding::{Encoding, PadType};
+    use crate::error::{DecodeError, RegistryError};
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    fn custom(name: &str) -> Encoding {
+        EncodingBuilder::new(name)
+            .alphabet("01234567")
+            .pad(PadType::DropPad('='))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lookup() {
+        let reg = Registry::new();
+        assert_eq!("base64", reg.get(" Base 64\n").unwrap().name);
+        assert_eq!("kanji", reg.get("HANJA").unwrap().name);
+        let err = reg.get("kanij").unwrap_err();
+        assert_eq!(
+            DecodeError::UnknownEncoding {
+                name: "kanij".to_owned(),
+                suggestion: Some("kanji".to_owned()),
+            },
+            err
+        );
+        let err = reg.get("zzzzzzzzzz").unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::UnknownEncoding {
+                suggestion: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_register() {
+        let reg = Registry::new();
+        let enc = reg.register(custom("Octal")).unwrap();
+        assert!(Arc::ptr_eq(&enc, &reg.get("octal").unwrap()));
+        reg.add_alias("base8", "OCTAL").unwrap();
+        assert_eq!("Octal", reg.get("base8").unwrap().name);
+
+        assert_eq!(
+            Some(RegistryError::NameTaken {
+                name: "octal".to_owned()
+            }),
+            reg.register(custom("octal")).err()
+        );
+        assert_eq!(
+            Err(RegistryError::NameTaken {
+                name: "hanzi".to_owned()
+            }),
+            reg.add_alias("hanzi", "octal")
+        );
+        assert_eq!(
+            Err(RegistryError::UnknownEncoding {
+                name: "base7".to_owned()
+            }),
+            reg.add_alias("septal", "base7")
+        );
+
+        assert!(reg.unregister("base8").is_some());
+        assert!(reg.get("octal").is_err());
+        assert!(reg.get("base8").is_err());
+        assert!(reg.unregister("octal").is_none());
+
+        let with_id = |name, id| {
+            EncodingBuilder::new(name)
+                .alphabet("01")
+                .pad(PadType::DropPad('='))
+                .id(id)
+                .build()
+                .unwrap()
+        };
+        assert_eq!("base64", reg.get_by_id(3).unwrap().name);
+        reg.register(with_id("b", 1000)).unwrap();
+        assert_eq!("b", reg.get_by_id(1000).unwrap().name);
+        assert_eq!(
+            Some(RegistryError::IdTaken { id: 1000 }),
+            reg.register(with_id("c", 1000)).err()
+        );
+        // the built-ins' own definitions keep their reserved ids
+        let mut def = reg.get("base64").unwrap().definition();
+        def.name = "my64".to_owned();
+        let copy = Encoding::try_from(def).unwrap();
+        assert_eq!(
+            Some(RegistryError::IdTaken { id: 3 }),
+            reg.register(copy).err()
+        );
+    }
+
+    #[test]
+    fn test_unregister_frees() {
+        let reg = Registry::new();
+        let enc = reg.register(custom("Octal")).unwrap();
+        reg.add_alias("base8", "octal").unwrap();
+        assert!(Arc::ptr_eq(&enc, &reg.get("BASE8").unwrap()));
+        let weak = Arc::downgrade(&enc);
+        drop(enc);
+
+        let removed = reg.unregister("base8").unwrap();
+        assert_eq!(1, Arc::strong_count(&removed));
+        drop(removed);
+        assert!(weak.upgrade().is_none());
+    }
+}
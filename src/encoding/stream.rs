@@ -0,0 +1,516 @@
+use std::io::{self, Read, Write};
+
+use super::{Encoding, Mode, PadType};
+use crate::error::DecodeError;
+use crate::repack::{BitOrder, BitQueue};
+
+/// How many bytes `DecoderReader` asks for at once
+const READ_CHUNK_LEN: usize = 8 * 1024;
+
+/// `Write` adaptor that encodes everything written to it into `inner`. The output is the same as
+/// `Encoding::encode` on the concatenation of all the written bytes.
+///
+/// Bytes that don't make up a whole block are kept until the next `write()`. The leftovers and the
+/// padding are written by `finish()` (or on drop, ignoring errors). Radix mode encodings can only
+/// be written out at the end, so they keep the whole input in memory.
+pub struct EncoderWriter<'a, W: Write> {
+    inner: Option<W>,
+    encoding: &'a Encoding,
+    pending: Vec<u8>,
+}
+
+impl<'a, W: Write> EncoderWriter<'a, W> {
+    pub fn new(inner: W, encoding: &'a Encoding) -> Self {
+        EncoderWriter {
+            inner: Some(inner),
+            encoding,
+            pending: vec![],
+        }
+    }
+
+    /// Write the leftover bytes and the padding, then return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_tail()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_tail(&mut self) -> io::Result<()> {
+        let text = self.encoding.encode(&self.pending);
+        self.pending.clear();
+        let inner = self.inner.as_mut().unwrap();
+        inner.write_all(text.as_bytes())?;
+        inner.flush()
+    }
+}
+
+impl<W: Write> Write for EncoderWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if let Some((block_len, _)) = self.encoding.stream_block() {
+            // whole blocks never get padding, so they can be encoded right away
+            let len = self.pending.len() - self.pending.len() % block_len;
+            if len > 0 {
+                let text = self.encoding.encode(&self.pending[..len]);
+                self.pending.drain(..len);
+                self.inner.as_mut().unwrap().write_all(text.as_bytes())?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes the underlying writer. This does not write the leftover bytes, see `finish()`.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for EncoderWriter<'_, W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_tail();
+        }
+    }
+}
+
+/// `Read` adaptor that decodes the UTF-8 text read from `inner`. The output is the same as
+/// `Encoding::decode` on the whole text, concatenated encodings included.
+///
+/// Repack-mode text is decoded char by char, keeping back the bytes that padding might still drop.
+/// Other modes decode as soon as what follows can't change the meaning of the text, i.e. whole
+/// blocks and whole concatenated parts. Decoding errors are reported as `io::ErrorKind::InvalidData`,
+/// with positions counting from the start of the whole text.
+pub struct DecoderReader<'a, R: Read> {
+    inner: R,
+    encoding: &'a Encoding,
+    /// read, but not yet decoded bytes (possibly ending in an incomplete UTF-8 sequence)
+    raw: Vec<u8>,
+    /// chars and bytes of text decoded so far
+    chars: usize,
+    bytes: usize,
+    /// bits of a repack-mode char, and how many decoded bytes `DropPad` padding can still drop
+    width: u8,
+    keep: usize,
+    part: Part,
+    decoded: Vec<u8>,
+    /// how much of `decoded` was already returned
+    pos: usize,
+    eof: bool,
+}
+
+/// Repack-mode state of the concatenated part being decoded
+struct Part {
+    /// the bits not output yet, repacked like `RepackIterator` does
+    bits: BitQueue,
+    nchars: usize,
+    pad_len: usize,
+    /// whether the part ended with a tail char
+    tail: bool,
+    /// the last decoded bytes, that `DropPad` padding might still drop
+    held: Vec<u8>,
+}
+
+impl Part {
+    fn new(order: BitOrder) -> Part {
+        Part {
+            bits: BitQueue::new(order),
+            nchars: 0,
+            pad_len: 0,
+            tail: false,
+            held: vec![],
+        }
+    }
+}
+
+impl<'a, R: Read> DecoderReader<'a, R> {
+    pub fn new(inner: R, encoding: &'a Encoding) -> Self {
+        let keep = match encoding.pad_char {
+            PadType::DropPad(_) => encoding.max_drop_pad(),
+            _ => 0,
+        };
+        DecoderReader {
+            inner,
+            encoding,
+            raw: vec![],
+            chars: 0,
+            bytes: 0,
+            width: encoding.bitcount(),
+            keep,
+            part: Part::new(encoding.bit_order),
+            decoded: vec![],
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Read another chunk and decode as much of the text as possible
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; READ_CHUNK_LEN];
+        let n = self.inner.read(&mut chunk)?;
+        self.eof = n == 0;
+        let mut raw = std::mem::take(&mut self.raw);
+        raw.extend_from_slice(&chunk[..n]);
+
+        let text = match std::str::from_utf8(&raw) {
+            Ok(text) => text,
+            Err(e) if e.error_len().is_none() && !self.eof => {
+                std::str::from_utf8(&raw[..e.valid_up_to()]).unwrap()
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let (len, res) = match self.encoding.mode {
+            Mode::Repack => {
+                let (mut res, start) = (Ok(()), self.bytes);
+                for (i, c) in text.char_indices() {
+                    res = self.push(c, start + i);
+                    if res.is_err() {
+                        break;
+                    }
+                }
+                if res.is_ok() && self.eof {
+                    res = self.end_part();
+                }
+                (text.len(), res)
+            }
+            _ => {
+                let len = match self.eof {
+                    true => text.len(),
+                    false => safe_prefix_len(self.encoding, text),
+                };
+                let res = self
+                    .encoding
+                    .decode_into(&text[..len], &mut self.decoded)
+                    .map_err(|e| e.offset(self.chars, self.bytes));
+                self.chars += text[..len].chars().count();
+                self.bytes += len;
+                (len, res)
+            }
+        };
+        raw.drain(..len);
+        self.raw = raw;
+        res.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Decode the repack-mode char `c`, found at `byte_offset`
+    fn push(&mut self, c: char, byte_offset: usize) -> Result<(), DecodeError> {
+        let enc = self.encoding;
+        let char_index = self.chars;
+        self.chars += 1;
+        self.bytes = byte_offset + c.len_utf8();
+        if c == *enc.pad_char {
+            self.part.pad_len += 1;
+            return Ok(());
+        }
+        // padding and tail chars end parts, see `Encoding::deconcat()`
+        if self.part.pad_len > 0 || self.part.tail {
+            self.end_part()?;
+        }
+        let u = enc.lookup.char_to_idx(c).ok_or(DecodeError::InvalidChar {
+            char: c,
+            char_index,
+            byte_offset,
+        })?;
+        let (width, u) = match enc.tail_of(u) {
+            Some(t) => {
+                self.part.tail = true;
+                (enc.tail_bits.unwrap(), t)
+            }
+            None => (self.width, u),
+        };
+
+        let part = &mut self.part;
+        part.nchars += 1;
+        part.bits.push(u, width);
+        while let Some(b) = part.bits.pop(8) {
+            part.held.push(b as u8);
+        }
+        if part.held.len() > self.keep {
+            let n = part.held.len() - self.keep;
+            self.decoded.extend(part.held.drain(..n));
+        }
+        Ok(())
+    }
+
+    /// Decode the rest of the current repack-mode part, now that its padding is known
+    fn end_part(&mut self) -> Result<(), DecodeError> {
+        let enc = self.encoding;
+        let mut part = std::mem::replace(&mut self.part, Part::new(enc.bit_order));
+        // `decode()` keeps none of the bits past a tail char's, and drops the other bytes
+        // according to the padding
+        if !part.tail {
+            // the last output is filled up with zeros
+            if let Some(b) = part.bits.flush(8) {
+                part.held.push(b as u8);
+            }
+            if matches!(enc.pad_char, PadType::DropPad(_)) && part.pad_len > self.keep {
+                return Err(DecodeError::InvalidPadding);
+            }
+            let drop = enc.drop_count(part.nchars, part.pad_len);
+            let len = part.held.len().checked_sub(drop);
+            part.held.truncate(len.ok_or(DecodeError::InvalidPadding)?);
+        }
+        self.decoded.append(&mut part.held);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecoderReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.decoded.len() && !self.eof {
+            self.decoded.clear();
+            self.pos = 0;
+            self.fill()?;
+        }
+        let n = std::cmp::min(buf.len(), self.decoded.len() - self.pos);
+        buf[..n].copy_from_slice(&self.decoded[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Byte length of the longest prefix of `text` that decodes the same, no matter what follows it,
+/// in radix and block-radix modes
+fn safe_prefix_len(encoding: &Encoding, text: &str) -> usize {
+    let pad = *encoding.pad_char;
+
+    // every concatenated part, except the last one
+    let mut len = 0;
+    let mut prev_pad = false;
+    for (i, c) in text.char_indices() {
+        if prev_pad && c != pad {
+            len = i;
+        }
+        prev_pad = c == pad;
+    }
+
+    // whole blocks of the last part. Its last char must stay, padding might still follow it
+    if let Some((_, block_chars)) = encoding.stream_block() {
+        let last = &text[len..];
+        let unpadded = last.find(pad).map_or(last, |i| &last[..i]);
+        let count = unpadded.chars().count().saturating_sub(1);
+        let nchars = count - count % block_chars;
+        len += unpadded
+            .char_indices()
+            .nth(nchars)
+            .map_or(unpadded.len(), |(i, _)| i);
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecoderReader, EncoderWriter};
+    use crate::encoding::builder::EncodingBuilder;
+    use crate::encoding::{get_encodings, Encoding, PadType};
+    use crate::error::DecodeError;
+    use std::io::{Read, Write};
+
+    /// Reader that returns at most `step` bytes per `read()`
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = *[self.step, buf.len(), self.data.len()]
+                .iter()
+                .min()
+                .unwrap();
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut acc = (0..=255).chain((0..=255).rev()).collect::<Vec<u8>>();
+        acc.extend(b"aaa");
+        acc
+    }
+
+    #[test]
+    fn test_encoder_matches_oneshot() {
+        let data = sample();
+        for c in get_encodings() {
+            for step in 1..8 {
+                let mut w = EncoderWriter::new(vec![], c);
+                for chunk in data.chunks(step) {
+                    w.write_all(chunk).unwrap();
+                }
+                let out = String::from_utf8(w.finish().unwrap()).unwrap();
+                assert_eq!(c.encode(&data), out, "Failed with encoding `{}`", c.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoder_pads_on_drop() {
+        let c = "base64".parse().unwrap();
+        let mut out = vec![];
+        EncoderWriter::new(&mut out, c).write_all(b"a").unwrap();
+        assert_eq!(b"YQ==", &out[..]);
+    }
+
+    #[test]
+    fn test_decoder_matches_oneshot() {
+        let data = sample();
+        for c in get_encodings() {
+            let text = c.encode(&data) + &c.encode(b"a") + &c.encode(&data);
+            let expected = c.decode(&text).unwrap();
+            for step in &[1, 2, 5, 4096] {
+                let inner = Trickle {
+                    data: text.as_bytes(),
+                    step: *step,
+                };
+                let mut acc = vec![];
+                DecoderReader::new(inner, c).read_to_end(&mut acc).unwrap();
+                assert_eq!(expected, acc, "Failed with encoding `{}`", c.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decoder_read_sizes() {
+        let data = sample();
+        let mut pad_types = vec![];
+        for c in get_encodings() {
+            let block_len = match c.stream_block() {
+                Some((block_len, _)) => c.encode(&data[..block_len]).len(),
+                None => continue,
+            };
+            let kind = std::mem::discriminant(&c.pad_char);
+            if !pad_types.contains(&kind) {
+                pad_types.push(kind);
+            }
+            let text = c.encode(&data) + &c.encode(b"a") + &c.encode(&data[..100]);
+            let expected = c.decode(&text).unwrap();
+            // both the text and the output, `size` bytes at a time
+            for size in 1..=2 * block_len {
+                let inner = Trickle {
+                    data: text.as_bytes(),
+                    step: size,
+                };
+                let mut r = DecoderReader::new(inner, c);
+                let mut acc = vec![];
+                let mut buf = vec![0; size];
+                loop {
+                    match r.read(&mut buf).unwrap() {
+                        0 => break,
+                        n => acc.extend_from_slice(&buf[..n]),
+                    }
+                }
+                assert_eq!(expected, acc, "Failed with encoding `{}`", c.name);
+            }
+        }
+        // BlockPad, DropPad, OmitPad and OnesPad
+        assert_eq!(4, pad_types.len());
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let c = "hex".parse().unwrap();
+        for text in &[&b"0F0X"[..], b"0F\xff"] {
+            let mut r = DecoderReader::new(*text, c);
+            let err = r.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        }
+        // truncated UTF-8 at the end of the input
+        let mut r = DecoderReader::new(&"伀".as_bytes()[..2], c);
+        assert!(r.read_to_end(&mut vec![]).is_err());
+    }
+
+    /// `DecoderReader` over `text`, read `step` bytes at a time
+    fn read_all(c: &Encoding, text: &str, step: usize) -> Result<Vec<u8>, DecodeError> {
+        let inner = Trickle {
+            data: text.as_bytes(),
+            step,
+        };
+        let mut acc = vec![];
+        match DecoderReader::new(inner, c).read_to_end(&mut acc) {
+            Ok(_) => Ok(acc),
+            Err(e) => Err(*e.into_inner().unwrap().downcast().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_decoder_error_positions() {
+        let pairs = [
+            ("hex", "00".repeat(5000) + "X", 10000, 10000),
+            ("kanji", "𠖫".repeat(3000) + "a", 3000, 12000),
+            ("base64", "YQ==".repeat(3000) + "-", 12000, 12000),
+            ("hangul-block", "륉퐢쯁".repeat(3000) + "a", 9000, 27000),
+        ];
+        for (name, text, char_index, byte_offset) in pairs {
+            let c = name.parse::<&Encoding>().unwrap();
+            let expected = DecodeError::InvalidChar {
+                char: text.chars().last().unwrap(),
+                char_index,
+                byte_offset,
+            };
+            assert_eq!(Err(expected.clone()), c.decode(&text));
+            for step in &[1, 4096] {
+                assert_eq!(Err(expected.clone()), read_all(c, &text, *step));
+            }
+        }
+        let c = "hangul-block".parse().unwrap();
+        let text = "륉퐢쯁".repeat(3000) + "흏흏흏";
+        let expected = Err(DecodeError::InvalidBlock { char_index: 9000 });
+        assert_eq!(expected, read_all(c, &text, 4096));
+    }
+
+    #[test]
+    fn test_decoder_matches_oneshot_on_bad_padding() {
+        let custom = EncodingBuilder::new("drop64")
+            .intervals(&[
+                (0x61, 0x7A),
+                (0x41, 0x5A),
+                (0x30, 0x39),
+                (0x2B, 0x2B),
+                (0x2F, 0x2F),
+            ])
+            .pad(PadType::DropPad('='))
+            .build()
+            .unwrap();
+        assert_eq!(Ok(vec![10, 81, 151]), read_all(&custom, "cLgx3=", 1));
+        // the pad chars would drop more bytes than the last char holds bits
+        assert_eq!(Err(DecodeError::InvalidPadding), custom.decode("cLgx3=="));
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            read_all(&custom, "cLgx3==", 1)
+        );
+
+        let names = [
+            "base64",
+            "kanji",
+            "hangul",
+            "hangul-block",
+            "basehangul",
+            "base65536",
+        ];
+        let codecs = names.iter().map(|x| x.parse::<&Encoding>().unwrap());
+        for c in codecs.chain(Some(&custom)) {
+            let pad = c.pad_char.to_string();
+            for n in 0..12 {
+                let text = c.encode(&sample()[..n]);
+                let unpadded = text.trim_end_matches(*c.pad_char);
+                let texts = [
+                    unpadded.to_owned(),
+                    text.clone() + &pad,
+                    text.clone() + &pad.repeat(3),
+                    unpadded.to_owned() + &pad + &text,
+                    pad.clone() + &text,
+                ];
+                for text in &texts {
+                    for step in &[1, 3, 4096] {
+                        let res = read_all(c, text, *step);
+                        assert_eq!(c.decode(text), res, "`{}` with `{}`", text, c.name);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Everything that can go wrong when looking up an encoding, or decoding with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A char that's not part of the charset. Positions count from the start of the whole input
+    InvalidChar {
+        char: char,
+        char_index: usize,
+        byte_offset: usize,
+    },
+    /// The padding doesn't match the length of the encoded text
+    InvalidPadding,
+    /// The last char holds set bits that don't belong to any byte (or clear ones, with `OnesPad`)
+    TrailingBits,
+    /// A pad char before the end of the text (i.e. a concatenation), in strict decoding
+    UnexpectedPad {
+        char_index: usize,
+        byte_offset: usize,
+    },
+    /// A block-radix block whose value doesn't fit in its bytes
+    InvalidBlock { char_index: usize },
+    /// The output slice is shorter than the `needed` bytes
+    BufferTooSmall { needed: usize },
+    /// The text doesn't start with a header, see `Encoding::encode_with_header()`
+    MissingHeader,
+    /// The header is of a newer (or unknown) format version
+    UnsupportedVersion(u8),
+    /// The header names an encoding that isn't registered
+    UnknownId(u16),
+    /// There's no encoding with this name. `suggestion` is the closest existing one, if any
+    UnknownEncoding {
+        name: String,
+        suggestion: Option<String>,
+    },
+    /// The decoded bytes aren't UTF-8, see `decode_utf8()`
+    InvalidUtf8 { valid_up_to: usize },
+}
+
+impl DecodeError {
+    /// The same error, with its positions moved `chars` chars (`bytes` bytes) further, for text
+    /// found that far into the input
+    #[cfg(feature = "std")]
+    pub(crate) fn offset(self, chars: usize, bytes: usize) -> Self {
+        match self {
+            Self::InvalidChar {
+                char,
+                char_index,
+                byte_offset,
+            } => Self::InvalidChar {
+                char,
+                char_index: char_index + chars,
+                byte_offset: byte_offset + bytes,
+            },
+            Self::UnexpectedPad {
+                char_index,
+                byte_offset,
+            } => Self::UnexpectedPad {
+                char_index: char_index + chars,
+                byte_offset: byte_offset + bytes,
+            },
+            Self::InvalidBlock { char_index } => Self::InvalidBlock {
+                char_index: char_index + chars,
+            },
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidChar {
+                char,
+                char_index,
+                byte_offset,
+            } => write!(
+                f,
+                "invalid char {:?} at char #{} (byte {})",
+                char, char_index, byte_offset
+            ),
+            Self::InvalidPadding => write!(f, "invalid padding"),
+            Self::TrailingBits => write!(f, "invalid trailing bits"),
+            Self::UnexpectedPad {
+                char_index,
+                byte_offset,
+            } => write!(
+                f,
+                "unexpected pad char at char #{} (byte {})",
+                char_index, byte_offset
+            ),
+            Self::InvalidBlock { char_index } => {
+                write!(f, "block at char #{} is out of range", char_index)
+            }
+            Self::BufferTooSmall { needed } => {
+                write!(f, "output buffer too small, {} bytes needed", needed)
+            }
+            Self::MissingHeader => write!(f, "missing encoding header"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported header version: {}", v),
+            Self::UnknownId(id) => write!(f, "no encoding with id {}", id),
+            Self::UnknownEncoding {
+                name,
+                suggestion: Some(s),
+            } => write!(f, "invalid encoding: {} (did you mean `{}`?)", name, s),
+            Self::UnknownEncoding { name, .. } => write!(f, "invalid encoding: {}", name),
+            Self::InvalidUtf8 { valid_up_to } => {
+                write!(f, "decoded data isn't UTF-8 past byte {}", valid_up_to)
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Reasons for `EncodingBuilder` to reject an encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// No alphabet was given
+    MissingAlphabet,
+    /// No pad char was given
+    MissingPad,
+    /// The alphabet has fewer than 2 chars
+    AlphabetTooSmall(usize),
+    /// A char appears more than once in the alphabet
+    DuplicateChar(char),
+    /// The pad char is also part of the alphabet
+    PadInAlphabet(char),
+    /// A code point that isn't a Unicode scalar value (i.e. a surrogate, or above U+10FFFF)
+    InvalidScalar(u32),
+    /// An interval whose start comes after its end
+    InvalidInterval(u32, u32),
+    /// An id too big for headers
+    InvalidId(u16),
+    /// An id below `MIN_CUSTOM_ID`, for an encoding that isn't the built-in one with that id
+    ReservedId(u16),
+    /// Tail chars of this many bits don't fit past the first `2^bitcount()` chars of the alphabet,
+    /// or can hold a whole byte past the data (or the mode isn't repack)
+    InvalidTailBits(u8),
+    /// Repack-mode chars of this many bits can leave a whole byte unused in the last char, so the
+    /// text can't tell whether the input held that byte. Needs `DropPad` or tail chars.
+    AmbiguousLength(u8),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAlphabet => write!(f, "no alphabet was given"),
+            Self::MissingPad => write!(f, "no pad char was given"),
+            Self::AlphabetTooSmall(n) => write!(f, "alphabet has {} chars, at least 2 needed", n),
+            Self::DuplicateChar(c) => write!(f, "char {:?} appears more than once", c),
+            Self::PadInAlphabet(c) => write!(f, "pad char {:?} is part of the alphabet", c),
+            Self::InvalidScalar(u) => write!(f, "U+{:04X} is not a valid char", u),
+            Self::InvalidInterval(a, b) => write!(f, "invalid interval U+{:04X}-U+{:04X}", a, b),
+            Self::InvalidId(id) => write!(f, "id {} is too big", id),
+            Self::ReservedId(id) => write!(f, "id {} is reserved for a built-in encoding", id),
+            Self::InvalidTailBits(n) => write!(f, "no room for tail chars of {} bits", n),
+            Self::AmbiguousLength(n) => write!(f, "{}-bit chars need DropPad or tail chars", n),
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+/// Reasons for `Registry` to reject a change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// The name (or alias) is already in use
+    NameTaken { name: String },
+    /// Another encoding has the same id
+    IdTaken { id: u16 },
+    /// Aliases can only point to registered encodings
+    UnknownEncoding { name: String },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NameTaken { name } => write!(f, "encoding name already taken: {}", name),
+            Self::IdTaken { id } => write!(f, "encoding id already taken: {}", id),
+            Self::UnknownEncoding { name } => write!(f, "invalid encoding: {}", name),
+        }
+    }
+}
+
+impl core::error::Error for RegistryError {}
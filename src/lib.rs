@@ -1,69 +1,95 @@
-use wasm_bindgen::prelude::*;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 mod encoding;
+mod error;
+mod radix;
 mod repack;
-use encoding::Encoding;
+#[cfg(feature = "wasm")]
+mod wasm;
+pub use encoding::builder::EncodingBuilder;
+pub use encoding::definition::EncodingDef;
+#[cfg(feature = "std")]
+pub use encoding::detect::detect;
+pub use encoding::diagnostics::Diagnostic;
+pub use encoding::header::{HEADER_VERSION, MAX_ENCODING_ID, MIN_CUSTOM_ID};
+pub use encoding::options::{DecodeOptions, EncodeOptions, Ignore, Wrap};
+#[cfg(feature = "std")]
+pub use encoding::registry::Registry;
+#[cfg(feature = "std")]
+pub use encoding::stream::{DecoderReader, EncoderWriter};
+pub use encoding::{CharSpace, EncodedLen, Encoding, Mode, PadType};
+pub use error::{BuildError, DecodeError, RegistryError};
+pub use repack::BitOrder;
+
+/// Decode `text` with the encoding named `charset`
+///
+/// ```
+/// assert_eq!(b"aa".to_vec(), basehanja::decode("YWE=", "base64").unwrap());
+/// ```
+pub fn decode(text: &str, charset: &str) -> Result<Vec<u8>, DecodeError> {
+    lookup(charset)?.decode(text)
+}
 
-#[wasm_bindgen(catch)]
-pub fn decode(text: &str, charset: &str) -> Result<Box<[u8]>, JsValue> {
-    let dec = charset.parse::<&Encoding>()?.decode(text)?;
-    Ok(dec.into_boxed_slice())
+/// `decode()`, for data that is UTF-8 text
+pub fn decode_utf8(text: &str, charset: &str) -> Result<String, DecodeError> {
+    String::from_utf8(decode(text, charset)?).map_err(|e| DecodeError::InvalidUtf8 {
+        valid_up_to: e.utf8_error().valid_up_to(),
+    })
 }
 
-#[wasm_bindgen(catch)]
-pub fn decode_utf8(text: &str, charset: &str) -> Result<String, JsValue> {
-    let dec = charset.parse::<&Encoding>()?.decode(text)?;
-    // or_else() is, comapred to or(), lazy. This permits calling this function from the examples, albeit
-    // in a hacky manner
-    String::from_utf8(dec).or_else(|_| Err(JsValue::from_str("Invalid UTF-8 encoding")))
+/// Decode a text that starts with a header, see `Encoding::encode_with_header()`
+#[cfg(feature = "std")]
+pub fn decode_any(text: &str) -> Result<Vec<u8>, DecodeError> {
+    Ok(Registry::global().decode_any(text)?.1)
 }
 
-#[wasm_bindgen(catch)]
-pub fn encode(text: Box<[u8]>, charset: &str) -> Result<String, JsValue> {
-    Ok(charset.parse::<&Encoding>()?.encode(&text))
+/// Encode `bytes` with the encoding named `charset`. Only fails if there's no such encoding.
+pub fn encode(bytes: &[u8], charset: &str) -> Result<String, DecodeError> {
+    Ok(lookup(charset)?.encode(bytes))
 }
 
-#[wasm_bindgen(catch)]
-pub fn encode_utf8(text: &str, charset: &str) -> Result<String, JsValue> {
-    Ok(charset.parse::<&Encoding>()?.encode(text.as_bytes()))
+/// `encode()` for text
+///
+/// ```
+/// assert_eq!("YWE=", basehanja::encode_utf8("aa", "base64").unwrap());
+/// ```
+pub fn encode_utf8(text: &str, charset: &str) -> Result<String, DecodeError> {
+    encode(text.as_bytes(), charset)
 }
 
-/// Helper struct that will be returned to JS
-#[derive(serde::Serialize)]
-struct EncodingDescription {
-    name: String,
-    description: String,
-    bitcount: u8,
+/// The encoding named `charset` in the global registry
+#[cfg(feature = "std")]
+fn lookup(charset: &str) -> Result<Arc<Encoding>, DecodeError> {
+    Registry::global().get(charset)
 }
 
-/// wasm-side: Return an array of objects
-#[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
-#[wasm_bindgen]
-pub fn get_encodings() -> Box<[JsValue]> {
-    encoding::get_encodings()
-        .iter()
-        .map(|x| EncodingDescription {
-            name: x.name.to_owned(),
-            description: x.long_name.to_owned(),
-            bitcount: x.bitcount(),
-        })
-        .map(|x| JsValue::from_serde(&x).unwrap())
-        .collect::<Vec<JsValue>>()
-        .into_boxed_slice()
+/// The built-in encoding named `charset`
+#[cfg(not(feature = "std"))]
+fn lookup(charset: &str) -> Result<&'static Encoding, DecodeError> {
+    charset.parse()
 }
 
-/// PC side: Return just a vector of names
-#[cfg(not(all(target_arch = "wasm32", not(target_os = "emscripten"))))]
-pub fn get_encodings() -> Vec<&'static str> {
-    encoding::get_encodings()
+/// Names of all the registered encodings
+#[cfg(feature = "std")]
+pub fn get_encodings() -> Vec<String> {
+    Registry::global()
+        .encodings()
         .iter()
-        .map(|x| x.name)
-        .collect::<Vec<&str>>()
+        .map(|x| x.name.clone())
+        .collect::<Vec<String>>()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::encoding::get_encodings as _get_enc;
+    use crate::PadType;
 
     const BLNS_URL: &str =
         "https://raw.githubusercontent.com/minimaxir/big-list-of-naughty-strings/master/blns.txt";
@@ -96,6 +122,26 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_native_errors() {
+        use crate::DecodeError;
+        assert_eq!("YWE=", crate::encode(b"aa", "base64").unwrap());
+        assert_eq!("aa", crate::decode_utf8("YWE=", "Base64").unwrap());
+        assert!(matches!(
+            crate::encode_utf8("aa", "base46"),
+            Err(DecodeError::UnknownEncoding { .. })
+        ));
+        assert!(matches!(
+            crate::decode("YW-=", "base64"),
+            Err(DecodeError::InvalidChar { char: '-', .. })
+        ));
+        assert_eq!(
+            Err(DecodeError::InvalidUtf8 { valid_up_to: 1 }),
+            crate::decode_utf8("61FF", "hex")
+        );
+        assert_eq!(Err(DecodeError::MissingHeader), crate::decode_any("YWE="));
+    }
+
     #[test]
     fn test_empty_inputs() {
         for c in _get_enc() {
@@ -116,7 +162,7 @@ mod tests {
 
     #[test]
     fn test_reflexivity_bytespace() {
-        let ref_dec = (0..255).chain(255..=0).collect::<Vec<u8>>();
+        let ref_dec = (0..255).collect::<Vec<u8>>();
         for c in _get_enc() {
             let enc = c.encode(&ref_dec);
             let dec = c.decode(&enc).unwrap();
@@ -128,7 +174,14 @@ mod tests {
     fn test_reflexivity_concatenated() {
         for i in 0..30 {
             let ref_dec = "a".repeat(i);
-            for c in _get_enc() {
+            // without padding, there's nothing to split on
+            let padded = _get_enc().iter().filter(|c| {
+                !matches!(
+                    c.definition().pad,
+                    PadType::OmitPad(_) | PadType::OnesPad(_)
+                )
+            });
+            for c in padded {
                 let enc = enc!(ref_dec, c);
                 let enc = enc.repeat(3);
                 let dec = dec!(&enc, c);
@@ -0,0 +1,153 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::repack::uVar;
+
+/// Write the input as one big-endian number in base `base` (like base58). Every leading zero byte
+/// is kept as a leading zero digit, so that `from_radix` can restore it.
+///
+/// This is the schoolbook algorithm, so it is quadratic in the input length.
+pub fn to_radix(bytes: &[u8], base: uVar) -> Vec<uVar> {
+    let base = base as u64;
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+
+    // little-endian digits
+    let mut digits: Vec<uVar> = vec![];
+    for &b in &bytes[zeros..] {
+        let mut carry = b as u64;
+        for d in digits.iter_mut() {
+            carry += (*d as u64) << 8;
+            *d = (carry % base) as uVar;
+            carry /= base;
+        }
+        while carry > 0 {
+            digits.push((carry % base) as uVar);
+            carry /= base;
+        }
+    }
+
+    let mut acc = vec![0; zeros];
+    acc.extend(digits.iter().rev());
+    acc
+}
+
+/// Inverse of `to_radix`. Digits must be smaller than `base`.
+pub fn from_radix(digits: &[uVar], base: uVar) -> Vec<u8> {
+    let base = base as u64;
+    let zeros = digits.iter().take_while(|&&d| d == 0).count();
+
+    // little-endian bytes
+    let mut bytes: Vec<u8> = vec![];
+    for &d in &digits[zeros..] {
+        let mut carry = d as u64;
+        for b in bytes.iter_mut() {
+            carry += (*b as u64) * base;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut acc = vec![0; zeros];
+    acc.extend(bytes.iter().rev());
+    acc
+}
+
+/// Longest block (in bytes) of the block-radix mode. Blocks are handled as `u64`s
+const MAX_BLOCK_LEN: usize = 8;
+
+/// Most digits a block can take (in base 2)
+pub const MAX_BLOCK_DIGITS: usize = 8 * MAX_BLOCK_LEN;
+
+/// Smallest count of base-`base` digits that can hold any `nbytes`-long number. Saturates to
+/// `usize::MAX`.
+pub fn digits_for(nbytes: usize, base: uVar) -> usize {
+    usize::try_from(digits_for_wide(nbytes, base)).unwrap_or(usize::MAX)
+}
+
+/// `digits_for()`, without saturating
+pub fn digits_for_wide(nbytes: usize, base: uVar) -> u128 {
+    if nbytes >= 16 {
+        // too big for a u128, go with ceil(8 * nbytes / log2(base)). Past 2^61 bytes the fixed
+        // point numbers would overflow, so some fractional bits of the log2 are dropped: rounding
+        // it down only rounds the count up
+        let bits = 8 * nbytes as u128;
+        let drop = (128 - bits.leading_zeros()).saturating_sub(128 - LOG2_FRAC_BITS);
+        return (bits << (LOG2_FRAC_BITS - drop)).div_ceil(log2_fixed(base) >> drop);
+    }
+    let max = 1u128 << (8 * nbytes);
+    let mut acc = 1u128;
+    let mut n = 0;
+    while acc < max {
+        // past u128, so surely past `max`
+        acc = acc.saturating_mul(base as u128);
+        n += 1;
+    }
+    n
+}
+
+/// Fractional bits of `log2_fixed()`
+pub const LOG2_FRAC_BITS: u32 = 64;
+
+/// log2(base) as a fixed-point number (see `LOG2_FRAC_BITS`), rounded down. No floats, so that it
+/// works without `std`.
+pub fn log2_fixed(base: uVar) -> u128 {
+    let int = 31 - base.leading_zeros();
+    // base / 2^int, in [1, 2), with 62 fractional bits. Squaring it doubles its log2, so the
+    // integer part of the square is the next bit
+    let mut m = (base as u128) << (62 - int);
+    let mut frac = 0u128;
+    for i in (0..LOG2_FRAC_BITS).rev() {
+        m = (m * m) >> 62;
+        if m >= 2 << 62 {
+            m >>= 1;
+            frac |= 1 << i;
+        }
+    }
+    ((int as u128) << LOG2_FRAC_BITS) | frac
+}
+
+/// Find the most efficient `(bytes, digits)` block for the block-radix mode. Ties go to the
+/// shortest block.
+///
+/// If `distinct_tails` is set, short blocks of different lengths must also have different digit
+/// counts, since the padding alone can't tell them apart (see `BlockPad`).
+pub fn best_block(base: uVar, distinct_tails: bool) -> (usize, usize) {
+    let mut best = (1, digits_for(1, base));
+    for nbytes in 2..=MAX_BLOCK_LEN {
+        let ndigits = digits_for(nbytes, base);
+        if distinct_tails && (1..nbytes).any(|r| digits_for(r, base) >= digits_for(r + 1, base)) {
+            continue;
+        }
+        // nbytes / ndigits > best.0 / best.1
+        if nbytes * best.1 > best.0 * ndigits {
+            best = (nbytes, ndigits);
+        }
+    }
+    best
+}
+
+/// Write a (short) block as a `ndigits`-long big-endian number
+pub fn encode_block(block: &[u8], ndigits: usize, base: uVar) -> impl Iterator<Item = uVar> {
+    let n = block.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    let base = base as u64;
+    // `ndigits` is minimal, so the highest power of `base` still fits in a u64
+    (0..ndigits)
+        .rev()
+        .map(move |i| (n / base.pow(i as u32) % base) as uVar)
+}
+
+/// Inverse of `encode_block`, as a number. Returns `None` if the digits don't fit in `nbytes`.
+pub fn decode_block(digits: &[uVar], nbytes: usize, base: uVar) -> Option<u64> {
+    let n = digits
+        .iter()
+        .fold(0u128, |acc, &d| acc * base as u128 + d as u128);
+    if n >= 1u128 << (8 * nbytes) {
+        return None;
+    }
+    Some(n as u64)
+}
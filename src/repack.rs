@@ -1,74 +1,333 @@
+use serde::{Deserialize, Serialize};
+
 /// Internal "container" type for variable-length uint
 #[allow(non_camel_case_types)]
 pub type uVar = u32;
 
-/// Iterator that repacks bits from ux to uy. u16 is used to represent input and output values.
+/// Order in which bits are packed, see `RepackIterator`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BitOrder {
+    /// the first bits are the most significant ones of every value, like in base64
+    #[default]
+    MsbFirst,
+    /// the first bits are the least significant ones of every value, like in little-endian
+    /// bitstreams
+    LsbFirst,
+}
+
+use BitOrder::*;
+
+/// Iterator that repacks bits from ux to uy, for any x and y from 1 to 32. `uVar` is used to
+/// represent input and output values; only the lower x bits of the inputs count.
 ///
-/// E.g: from u8->u6 (or vice versa)
+/// E.g: from u8->u6 (or vice versa), MSB-first
 ///
 /// `11111100 00001111` -> `111111 000000 111100`
+///
+/// And LSB-first, where the first bits are the rightmost ones
+///
+/// `11111100 00001111` -> `111100 111111 000000`
 pub struct RepackIterator<T: Iterator> {
-    iband: std::iter::Peekable<T>,
-    cbits: u8,
+    iband: T,
+    bits: BitQueue,
     isize: u8,
     osize: u8,
 }
 
+/// Widest symbols `RepackIterator` handles
+pub const MAX_WIDTH: u8 = 32;
+
 impl<T: Iterator<Item = uVar>> RepackIterator<T> {
-    pub fn new<F>(iband: F, isize: u8, osize: u8) -> RepackIterator<T>
+    /// Panics unless both sizes are within `1..=MAX_WIDTH`
+    pub fn new<F>(iband: F, isize: u8, osize: u8, order: BitOrder) -> RepackIterator<T>
     where
         F: IntoIterator<Item = uVar, IntoIter = T>,
     {
+        assert!(
+            (1..=MAX_WIDTH).contains(&isize) && (1..=MAX_WIDTH).contains(&osize),
+            "can't repack u{} to u{}",
+            isize,
+            osize
+        );
         RepackIterator {
-            iband: iband.into_iter().peekable(),
-            cbits: 0,
-            isize: isize,
-            osize: osize,
+            iband: iband.into_iter(),
+            bits: BitQueue::new(order),
+            isize,
+            osize,
         }
     }
 }
 
-fn take_bits(n: uVar, nsize: u8, count: u8, skip: u8) -> uVar {
-    // 01234567
-    // __XXXXX_ -> ___XXXXX
-    // 76543210
-    let from = nsize - skip;
-    let to = nsize - skip - count;
-    let mask = ((1 << from) - 1) - ((1 << to) - 1);
-    (n & mask) >> to
+fn mask(bits: u8) -> u64 {
+    (1 << bits) - 1
 }
 
 impl<T: Iterator<Item = uVar>> Iterator for RepackIterator<T> {
     type Item = T::Item;
 
     fn next(&mut self) -> Option<uVar> {
-        if let None = self.iband.peek() {
-            return None;
+        loop {
+            if let Some(out) = self.bits.pop(self.osize) {
+                return Some(out);
+            }
+            match self.iband.next() {
+                Some(n) => self.bits.push(n, self.isize),
+                // the last output is filled up with zeros
+                None => return self.bits.flush(self.osize),
+            }
         }
+    }
+}
+
+/// The bits buffered by `RepackIterator`, for repacking symbols that come in one at a time (e.g.
+/// from a stream). Values go in and out in `order`, each of its own width.
+pub struct BitQueue {
+    /// the `nbits` bits not output yet, as the lower bits
+    acc: u64,
+    nbits: u8,
+    order: BitOrder,
+}
 
-        let mut acc: uVar = 0;
-        let mut aln: u8 = 0;
+impl BitQueue {
+    pub fn new(order: BitOrder) -> BitQueue {
+        BitQueue {
+            acc: 0,
+            nbits: 0,
+            order,
+        }
+    }
 
+    /// Append the lower `width` bits of `x`. Up to `MAX_WIDTH` bits can be pushed on top of fewer
+    /// than `MAX_WIDTH` bits.
+    pub fn push(&mut self, x: uVar, width: u8) {
         // 01234567 01234567 01234567
         // _____XXX XXXXXXXX XXXX____
-        while aln < self.osize {
-            if self.iband.peek().is_none() {
-                return Some(acc << (self.osize - aln));
+        // at most 31 + 32 bits are buffered
+        let x = x as u64 & mask(width);
+        self.acc = match self.order {
+            MsbFirst => (self.acc << width) | x,
+            LsbFirst => self.acc | (x << self.nbits),
+        };
+        self.nbits += width;
+    }
+
+    /// Take out the next `width` bits, if there are that many
+    pub fn pop(&mut self, width: u8) -> Option<uVar> {
+        if self.nbits < width {
+            return None;
+        }
+        self.nbits -= width;
+        let out = match self.order {
+            MsbFirst => self.acc >> self.nbits,
+            LsbFirst => {
+                let out = self.acc;
+                self.acc >>= width;
+                out
             }
-            let crt_n = *self.iband.peek().unwrap();
+        };
+        self.acc &= mask(self.nbits);
+        Some((out & mask(width)) as uVar)
+    }
 
-            let munch = std::cmp::min(self.osize - aln, self.isize - self.cbits);
-            acc <<= munch;
-            acc |= take_bits(crt_n, self.isize, munch, self.cbits);
-            self.cbits += munch;
-            aln += munch;
+    /// Take out the bits left (fewer than `width`, after `pop()`), filled up with zeros to `width`
+    /// bits. `None` if there are none.
+    pub fn flush(&mut self, width: u8) -> Option<uVar> {
+        if self.nbits == 0 {
+            return None;
+        }
+        let out = match self.order {
+            MsbFirst => self.acc << (width - self.nbits),
+            LsbFirst => self.acc,
+        };
+        self.acc = 0;
+        self.nbits = 0;
+        Some((out & mask(width)) as uVar)
+    }
+}
+
+/// Bits repacked per iteration by `pack_words()` and `unpack_words()`: as many `lcm(8, width)`-bit
+/// groups as fit in a u64, so that words start and end on both byte and symbol boundaries. `None`
+/// if not even one group fits (e.g. 13-bit symbols).
+pub fn word_bits(width: u8) -> Option<u32> {
+    let width = width as u32;
+    let group = (1..=8).map(|x| x * width).find(|x| x % 8 == 0)?;
+    match group <= 64 {
+        true => Some(64 / group * group),
+        false => None,
+    }
+}
 
-            if self.cbits == self.isize {
-                self.iband.next();
-                self.cbits = 0;
+/// `RepackIterator::new(bytes, 8, width, order)` a u64 at a time, over the longest prefix
+/// of `bytes` made of whole words (see `word_bits()`). Returns the count of bytes consumed; the
+/// rest repacks the same on its own.
+pub fn pack_words<F: FnMut(uVar)>(bytes: &[u8], width: u8, order: BitOrder, emit: F) -> usize {
+    // one copy per width, so that the inner loop is unrolled
+    macro_rules! dispatch {
+        ($($w:literal)*) => {
+            match width {
+                $($w => pack_words_of::<$w, F>(bytes, order, emit),)*
+                _ => 0,
+            }
+        };
+    }
+    dispatch!(1 2 3 4 5 6 7 8 10 12 14 16)
+}
+
+fn pack_words_of<const W: u8, F: FnMut(uVar)>(bytes: &[u8], order: BitOrder, mut emit: F) -> usize {
+    let bits = match word_bits(W) {
+        Some(x) => x,
+        None => return 0,
+    };
+    let (nbytes, nsyms) = (bits as usize / 8, bits / W as u32);
+    let mask = mask(W);
+    let mut chunks = bytes.chunks_exact(nbytes);
+    match order {
+        MsbFirst => {
+            for chunk in &mut chunks {
+                let mut buf = [0u8; 8];
+                buf[8 - nbytes..].copy_from_slice(chunk);
+                let word = u64::from_be_bytes(buf);
+                for i in (0..nsyms).rev() {
+                    emit(((word >> (i * W as u32)) & mask) as uVar);
+                }
+            }
+        }
+        LsbFirst => {
+            for chunk in &mut chunks {
+                let mut buf = [0u8; 8];
+                buf[..nbytes].copy_from_slice(chunk);
+                let word = u64::from_le_bytes(buf);
+                for i in 0..nsyms {
+                    emit(((word >> (i * W as u32)) & mask) as uVar);
+                }
+            }
+        }
+    }
+    bytes.len() - chunks.remainder().len()
+}
+
+/// `RepackIterator::new(symbols, width, 8, order)` a u64 at a time, for as many whole words
+/// (see `word_bits()`) as `symbols` holds and `out` has room for. Like `RepackIterator`, only the
+/// lower `width` bits of every symbol count. Returns the count of symbols consumed and of bytes
+/// written; the rest repacks the same on its own.
+pub fn unpack_words<T: Copy + Into<u64>>(
+    symbols: &[T],
+    width: u8,
+    order: BitOrder,
+    out: &mut [u8],
+) -> (usize, usize) {
+    let bits = match word_bits(width) {
+        Some(x) => x,
+        None => return (0, 0),
+    };
+    let (nbytes, nsyms) = (bits as usize / 8, bits as usize / width as usize);
+    let mask = mask(width);
+    let nwords = core::cmp::min(symbols.len() / nsyms, out.len() / nbytes);
+    let words = symbols
+        .chunks_exact(nsyms)
+        .zip(out.chunks_exact_mut(nbytes));
+    for (syms, bytes) in words.take(nwords) {
+        let fold = |acc: u64, &x: &T| (acc << width) | (x.into() & mask);
+        match order {
+            MsbFirst => {
+                let word = syms.iter().fold(0, fold);
+                bytes.copy_from_slice(&word.to_be_bytes()[8 - nbytes..]);
+            }
+            LsbFirst => {
+                let word = syms.iter().rev().fold(0, fold);
+                bytes.copy_from_slice(&word.to_le_bytes()[..nbytes]);
             }
         }
+    }
+    (nwords * nsyms, nwords * nbytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{uVar, BitOrder, RepackIterator, MAX_WIDTH};
+
+    /// Repack bit by bit
+    fn reference(syms: &[uVar], isize: u8, osize: u8, order: BitOrder) -> Vec<uVar> {
+        // positions of the bits of a value, in order
+        let positions = |size: u8| -> Vec<u8> {
+            match order {
+                BitOrder::MsbFirst => (0..size).rev().collect(),
+                BitOrder::LsbFirst => (0..size).collect(),
+            }
+        };
+        let bits = syms
+            .iter()
+            .flat_map(|&x| {
+                positions(isize)
+                    .into_iter()
+                    .map(move |i| (x as u64 >> i) & 1)
+            })
+            .collect::<Vec<u64>>();
+        bits.chunks(osize as usize)
+            .map(|x| {
+                let n = x.iter().zip(positions(osize)).map(|(&b, i)| b << i);
+                n.sum::<u64>() as uVar
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_doc_example() {
+        let syms = |order| RepackIterator::new(vec![0xFC, 0x0F], 8, 6, order).collect::<Vec<_>>();
+        let expected: Vec<uVar> = vec![0b111111, 0b000000, 0b111100];
+        assert_eq!(expected, syms(BitOrder::MsbFirst));
+        let expected: Vec<uVar> = vec![0b111100, 0b111111, 0b000000];
+        assert_eq!(expected, syms(BitOrder::LsbFirst));
+    }
+
+    #[test]
+    fn test_every_width() {
+        // xorshift, so that every width gets all kinds of bit patterns
+        let mut state = 0x2545_f491_u32;
+        let noise = (0..40)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            })
+            .chain([0, uVar::MAX, 1, 1 << 31])
+            .collect::<Vec<uVar>>();
+        for isize in 1..=MAX_WIDTH {
+            let keep = (1u64 << isize) - 1;
+            let clean = noise.iter().map(|&x| (x as u64 & keep) as uVar);
+            let clean = clean.collect::<Vec<uVar>>();
+            let pairs = (1..=MAX_WIDTH)
+                .flat_map(|x| [BitOrder::MsbFirst, BitOrder::LsbFirst].map(|order| (x, order)));
+            for (osize, order) in pairs {
+                let repack = |syms: Vec<uVar>, isize, osize| {
+                    RepackIterator::new(syms, isize, osize, order).collect::<Vec<uVar>>()
+                };
+                for n in (0..12).chain(Some(noise.len())) {
+                    let out = repack(noise[..n].to_vec(), isize, osize);
+                    let expected = reference(&clean[..n], isize, osize, order);
+                    let msg = format!("u{} -> u{} {:?}, {} symbols", isize, osize, order, n);
+                    assert_eq!(expected, out, "{}", msg);
+
+                    // and back, give or take trailing zeros
+                    let back = repack(out, osize, isize);
+                    assert_eq!(&clean[..n], &back[..n], "{}", msg);
+                    assert!(back[n..].iter().all(|&x| x == 0), "{}", msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "can't repack u8 to u33")]
+    fn test_too_wide() {
+        RepackIterator::new(vec![1], 8, 33, BitOrder::MsbFirst);
+    }
 
-        Some(acc)
+    #[test]
+    #[should_panic(expected = "can't repack u0 to u8")]
+    fn test_empty_width() {
+        RepackIterator::new(vec![1], 0, 8, BitOrder::LsbFirst);
     }
 }
@@ -0,0 +1,57 @@
+//! JS bindings. Errors become `JsError`s, which can only be built on wasm.
+
+use wasm_bindgen::prelude::*;
+
+use crate::Registry;
+
+#[wasm_bindgen]
+pub fn decode(text: &str, charset: &str) -> Result<Box<[u8]>, JsError> {
+    Ok(crate::decode(text, charset)?.into_boxed_slice())
+}
+
+#[wasm_bindgen]
+pub fn decode_utf8(text: &str, charset: &str) -> Result<String, JsError> {
+    Ok(crate::decode_utf8(text, charset)?)
+}
+
+/// Decode a text that starts with a header, see `Encoding::encode_with_header()`
+#[wasm_bindgen]
+pub fn decode_any(text: &str) -> Result<Box<[u8]>, JsError> {
+    let (_, dec) = Registry::global().decode_any(text)?;
+    Ok(dec.into_boxed_slice())
+}
+
+#[wasm_bindgen]
+pub fn encode(text: Box<[u8]>, charset: &str) -> Result<String, JsError> {
+    Ok(crate::encode(&text, charset)?)
+}
+
+#[wasm_bindgen]
+pub fn encode_utf8(text: &str, charset: &str) -> Result<String, JsError> {
+    Ok(crate::encode_utf8(text, charset)?)
+}
+
+/// Helper struct that will be returned to JS
+#[derive(serde::Serialize)]
+struct EncodingDescription {
+    name: String,
+    description: String,
+    bitcount: u8,
+}
+
+/// Return an array of objects
+#[wasm_bindgen]
+#[allow(deprecated)] // from_serde() is fine for a handful of plain structs
+pub fn get_encodings() -> Box<[JsValue]> {
+    Registry::global()
+        .encodings()
+        .iter()
+        .map(|x| EncodingDescription {
+            name: x.name.clone(),
+            description: x.long_name.clone(),
+            bitcount: x.bitcount(),
+        })
+        .map(|x| JsValue::from_serde(&x).unwrap())
+        .collect::<Vec<JsValue>>()
+        .into_boxed_slice()
+}